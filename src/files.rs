@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// How many leading bytes are looked at to recognise a file's type.
+const SNIFF_LENGTH: u64 = 8192;
+
+/// What was found at an attachment's path at the last check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileKind {
+    Missing,
+    Directory,
+    /// A file, with its MIME type when the content was recognised.
+    File(Option<&'static str>),
+}
+
+/// Results of the last check, by attachment path.
+pub type Files = HashMap<PathBuf, FileKind>;
+
+impl FileKind {
+    pub fn of(path: &Path) -> FileKind {
+        match path.metadata() {
+            Err(_) => FileKind::Missing,
+            Ok(metadata) if metadata.is_dir() => FileKind::Directory,
+            Ok(_) => FileKind::File(sniff(path)),
+        }
+    }
+}
+
+/// Guesses the MIME type from the content, treating anything without NUL
+/// bytes as text.
+fn sniff(path: &Path) -> Option<&'static str> {
+    let mut head = Vec::new();
+
+    File::open(path)
+        .and_then(|file| file.take(SNIFF_LENGTH).read_to_end(&mut head))
+        .ok()?;
+
+    match infer::get(&head) {
+        Some(kind) => Some(kind.mime_type()),
+        None if !head.is_empty() && !head.contains(&0) => Some("text/plain"),
+        None => None,
+    }
+}
+
+pub async fn inspect(paths: Vec<PathBuf>) -> Files {
+    paths
+        .into_iter()
+        .map(|path| {
+            let kind = FileKind::of(&path);
+            (path, kind)
+        })
+        .collect()
+}
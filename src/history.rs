@@ -0,0 +1,49 @@
+use todo_core::TaskStore;
+
+/// How many changes can be undone before the oldest ones are forgotten.
+const LIMIT: usize = 100;
+
+/// Snapshots of the task list taken before each change, so the change can be
+/// undone and then redone again.
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<TaskStore>,
+    redo: Vec<TaskStore>,
+    recorded: u64,
+}
+
+impl History {
+    /// Remembers `store` as it was before a change.
+    pub fn record(&mut self, store: &TaskStore) {
+        self.undo.push(store.clone());
+
+        if self.undo.len() > LIMIT {
+            self.undo.remove(0);
+        }
+
+        self.redo.clear();
+        self.recorded += 1;
+    }
+
+    /// How many changes were recorded so far, to tell whether another one
+    /// came since.
+    pub fn recorded(&self) -> u64 {
+        self.recorded
+    }
+
+    /// Returns the task list before the last change, given the current one.
+    pub fn undo(&mut self, current: &TaskStore) -> Option<TaskStore> {
+        let store = self.undo.pop()?;
+        self.redo.push(current.clone());
+
+        Some(store)
+    }
+
+    /// Returns the task list after the last undone change.
+    pub fn redo(&mut self, current: &TaskStore) -> Option<TaskStore> {
+        let store = self.redo.pop()?;
+        self.undo.push(current.clone());
+
+        Some(store)
+    }
+}
@@ -0,0 +1,225 @@
+//! Icons shown for attachments, picked by file extension or, failing that,
+//! by the MIME type sniffed from the file's content.
+//!
+//! Teams can map more types in `icons.json` in the config directory:
+//!
+//! ```json
+//! {
+//!     "extensions": { "docx": "word.png", "pdf": "/usr/share/icons/pdf.png" },
+//!     "mime_types": { "application/pdf": "pdf.png" }
+//! }
+//! ```
+//!
+//! Relative icon paths are resolved against the config directory, and
+//! entries there take precedence over the built-in ones.
+//!
+//! The built-in icons are compiled into the binary. A `"theme"` directory
+//! may replace any of them with a PNG of the same file name, e.g.
+//! `"theme": "themes/dark"` with a `themes/dark/folder-48.png` inside.
+
+use crate::files::FileKind;
+use iced::image::Handle;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use todo_core::{Attachment, Target};
+
+const BUILT_IN_EXTENSIONS: [(&str, &str); 19] = [
+    ("txt", "icons8-txt-48.png"),
+    ("log", "icons8-txt-48.png"),
+    ("md", "icons8-txt-48.png"),
+    ("xls", "icons8-xls-48.png"),
+    ("xlsx", "icons8-xls-48.png"),
+    ("csv", "icons8-xls-48.png"),
+    ("jpg", "icons8-jpg-48.png"),
+    ("jpeg", "icons8-jpg-48.png"),
+    ("png", "icons8-jpg-48.png"),
+    ("gif", "icons8-jpg-48.png"),
+    ("bmp", "icons8-jpg-48.png"),
+    ("webp", "icons8-jpg-48.png"),
+    ("exe", "icons8-exe-48.png"),
+    ("msi", "icons8-exe-48.png"),
+    ("zip", "icons8-zip-48.png"),
+    ("gz", "icons8-zip-48.png"),
+    ("7z", "icons8-zip-48.png"),
+    ("rar", "icons8-zip-48.png"),
+    ("tar", "icons8-zip-48.png"),
+];
+
+const BUILT_IN_MIME_TYPES: [(&str, &str); 12] = [
+    ("text/plain", "icons8-txt-48.png"),
+    ("image/jpeg", "icons8-jpg-48.png"),
+    ("image/png", "icons8-jpg-48.png"),
+    ("image/gif", "icons8-jpg-48.png"),
+    ("image/bmp", "icons8-jpg-48.png"),
+    ("image/webp", "icons8-jpg-48.png"),
+    ("application/zip", "icons8-zip-48.png"),
+    ("application/gzip", "icons8-zip-48.png"),
+    ("application/x-7z-compressed", "icons8-zip-48.png"),
+    ("application/vnd.rar", "icons8-zip-48.png"),
+    (
+        "application/vnd.microsoft.portable-executable",
+        "icons8-exe-48.png",
+    ),
+    ("application/x-executable", "icons8-exe-48.png"),
+];
+
+/// Every built-in icon, by file name.
+const EMBEDDED: [(&str, &[u8]); 8] = [
+    (
+        "icons8-txt-48.png",
+        include_bytes!("../icons/icons8-txt-48.png"),
+    ),
+    (
+        "icons8-xls-48.png",
+        include_bytes!("../icons/icons8-xls-48.png"),
+    ),
+    (
+        "icons8-jpg-48.png",
+        include_bytes!("../icons/icons8-jpg-48.png"),
+    ),
+    (
+        "icons8-exe-48.png",
+        include_bytes!("../icons/icons8-exe-48.png"),
+    ),
+    (
+        "icons8-zip-48.png",
+        include_bytes!("../icons/icons8-zip-48.png"),
+    ),
+    ("file-48.png", include_bytes!("../icons/file-48.png")),
+    ("folder-48.png", include_bytes!("../icons/folder-48.png")),
+    ("link-48.png", include_bytes!("../icons/link-48.png")),
+];
+
+const GENERIC: &str = "file-48.png";
+const FOLDER: &str = "folder-48.png";
+const LINK: &str = "link-48.png";
+
+#[derive(Debug, Clone)]
+pub struct Icons {
+    /// Keyed by lowercase extension, without the dot.
+    extensions: HashMap<String, Handle>,
+    mime_types: HashMap<String, Handle>,
+    generic: Handle,
+    folder: Handle,
+    link: Handle,
+}
+
+/// Layout of `icons.json`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Config {
+    extensions: HashMap<String, PathBuf>,
+    mime_types: HashMap<String, PathBuf>,
+    /// Directory whose PNGs replace the built-in icons of the same name.
+    theme: Option<PathBuf>,
+}
+
+impl Default for Icons {
+    fn default() -> Self {
+        Icons::built_in(None)
+    }
+}
+
+impl Icons {
+    /// The built-in icons extended by the user's `icons.json`, if any.
+    pub fn load() -> Icons {
+        let Some(dir) = config_dir() else {
+            return Icons::default();
+        };
+
+        let path = dir.join("icons.json");
+        let config = match std::fs::read_to_string(&path) {
+            Ok(contents) => match serde_json::from_str::<Config>(&contents) {
+                Ok(config) => config,
+                Err(error) => {
+                    eprintln!("Ignoring {}: {}", path.display(), error);
+                    return Icons::default();
+                }
+            },
+            Err(_) => return Icons::default(),
+        };
+
+        let theme = config.theme.map(|theme| dir.join(theme));
+        let mut icons = Icons::built_in(theme.as_deref());
+
+        for (extension, icon) in config.extensions {
+            let extension = extension.trim_start_matches('.').to_lowercase();
+
+            icons
+                .extensions
+                .insert(extension, Handle::from_path(dir.join(icon)));
+        }
+
+        for (mime_type, icon) in config.mime_types {
+            icons
+                .mime_types
+                .insert(mime_type.to_lowercase(), Handle::from_path(dir.join(icon)));
+        }
+
+        icons
+    }
+
+    /// The icon for `attachment`, given what was last found at its path.
+    pub fn get(&self, attachment: &Attachment, kind: Option<&FileKind>) -> Handle {
+        let path = match &attachment.target {
+            Target::Url { .. } => return self.link.clone(),
+            Target::LocalFile { path } => path,
+        };
+
+        if let Some(FileKind::Directory) = kind {
+            return self.folder.clone();
+        }
+
+        let by_extension = path.extension().and_then(|extension| {
+            self.extensions
+                .get(&extension.to_string_lossy().to_lowercase())
+        });
+
+        let by_mime_type = || match kind {
+            Some(FileKind::File(Some(mime_type))) => self.mime_types.get(*mime_type),
+            _ => None,
+        };
+
+        by_extension
+            .or_else(by_mime_type)
+            .unwrap_or(&self.generic)
+            .clone()
+    }
+
+    /// The built-in icons, each taken from `theme` when it has a file of the
+    /// same name.
+    fn built_in(theme: Option<&Path>) -> Icons {
+        let handles = EMBEDDED
+            .iter()
+            .map(|(name, bytes)| {
+                let handle = match theme.map(|theme| theme.join(name)) {
+                    Some(path) if path.is_file() => Handle::from_path(path),
+                    _ => Handle::from_memory(bytes.to_vec()),
+                };
+
+                (*name, handle)
+            })
+            .collect::<HashMap<_, _>>();
+
+        let handle = |name: &str| handles[name].clone();
+
+        Icons {
+            extensions: BUILT_IN_EXTENSIONS
+                .iter()
+                .map(|(extension, icon)| (extension.to_string(), handle(icon)))
+                .collect(),
+            mime_types: BUILT_IN_MIME_TYPES
+                .iter()
+                .map(|(mime_type, icon)| (mime_type.to_string(), handle(icon)))
+                .collect(),
+            generic: handle(GENERIC),
+            folder: handle(FOLDER),
+            link: handle(LINK),
+        }
+    }
+}
+
+fn config_dir() -> Option<PathBuf> {
+    todo_core::project_dirs().map(|project_dirs| project_dirs.config_dir().to_path_buf())
+}
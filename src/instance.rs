@@ -0,0 +1,348 @@
+//! Keeps two instances of the app from writing the same todo file.
+//!
+//! The instance that opens a file first leaves a `.lock` file next to it,
+//! only readable by the user, holding its process id, the address of a
+//! socket it listens on and a token. A later launch on the same file sends
+//! the token to that socket, checks that the answer is the owner's process
+//! id, hands its arguments over and quits, or opens the file read-only when
+//! it cannot. Connections without the token are turned away.
+
+use async_std::io::prelude::BufReadExt;
+use async_std::io::{ReadExt, WriteExt};
+use iced::futures::stream::{self, BoxStream};
+use serde::{Deserialize, Serialize};
+use std::hash::Hash;
+use std::io::{self, BufRead, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// How long a lock file may stay unreadable while its owner is writing it.
+const STARTING_GRACE: Duration = Duration::from_secs(5);
+
+/// How long to wait on the other end of the socket.
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The right to write a todo file, given up when dropped.
+#[derive(Debug)]
+pub struct Lock {
+    file: PathBuf,
+    listener: TcpListener,
+    token: String,
+}
+
+/// What is written in the lock file.
+#[derive(Debug, Serialize, Deserialize)]
+struct Owner {
+    pid: u32,
+    address: SocketAddr,
+    /// What a later launch sends first, to show it could read the lock.
+    token: String,
+}
+
+/// The outcome of trying to lock a todo file.
+#[derive(Debug)]
+pub enum Acquired {
+    Locked(Lock),
+    /// Another instance has the file open and answers on this connection.
+    Running(TcpStream),
+    Unavailable(io::Error),
+}
+
+/// Whether this instance may write the todo file it shows.
+#[derive(Debug)]
+pub enum Access {
+    Locked(Lock),
+    /// Changes are kept in the window only; with the reason, unless it was
+    /// asked for.
+    ReadOnly(Option<String>),
+}
+
+impl Default for Access {
+    fn default() -> Self {
+        Access::ReadOnly(None)
+    }
+}
+
+impl Access {
+    /// Locks the todo file at `path` from an instance already running, which
+    /// has no one to forward to: the file is read-only while another window
+    /// has it.
+    pub fn open(path: &Path) -> Access {
+        match acquire(path) {
+            Acquired::Locked(lock) => Access::Locked(lock),
+            Acquired::Running(_) => {
+                Access::ReadOnly(Some(String::from("it is open in another window")))
+            }
+            Acquired::Unavailable(error) => {
+                Access::ReadOnly(Some(format!("it cannot be locked: {}", error)))
+            }
+        }
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        matches!(self, Access::ReadOnly(_))
+    }
+
+    /// Whether this is the lock of the todo file at `path`.
+    pub fn guards(&self, path: &Path) -> bool {
+        matches!(self, Access::Locked(lock) if lock.file == lock_file(path))
+    }
+
+    /// The files later launches ask to attach, as they arrive.
+    pub fn listen(&self) -> Option<Listen> {
+        let Access::Locked(lock) = self else {
+            return None;
+        };
+
+        match lock.listener.try_clone() {
+            Ok(listener) => Some(Listen {
+                file: lock.file.clone(),
+                listener,
+                token: lock.token.clone(),
+            }),
+            Err(error) => {
+                eprintln!("Cannot listen for other launches: {}", error);
+                None
+            }
+        }
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        // Leave the file alone if it was taken over meanwhile.
+        if read_owner(&self.file).is_some_and(|owner| owner.pid == std::process::id()) {
+            let _ = std::fs::remove_file(&self.file);
+        }
+    }
+}
+
+/// Locks the todo file at `path`, taking over a lock whose owner is gone.
+pub fn acquire(path: &Path) -> Acquired {
+    let file = lock_file(path);
+
+    let listener = match TcpListener::bind(("127.0.0.1", 0)) {
+        Ok(listener) => listener,
+        Err(error) => return Acquired::Unavailable(error),
+    };
+
+    let token = uuid::Uuid::new_v4().simple().to_string();
+
+    // Once to take the lock, once more after clearing a stale one.
+    for _ in 0..2 {
+        match create(&file, &listener, &token) {
+            Ok(()) => {
+                return Acquired::Locked(Lock {
+                    file,
+                    listener,
+                    token,
+                })
+            }
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(error) => return Acquired::Unavailable(error),
+        }
+
+        match read_owner(&file) {
+            Some(owner) if is_running(owner.pid) => match greet(&owner) {
+                Ok(stream) => return Acquired::Running(stream),
+                // Alive, but busy or still starting.
+                Err(error) if is_timeout(&error) => {
+                    return Acquired::Unavailable(io::Error::new(
+                        error.kind(),
+                        "another instance does not answer",
+                    ));
+                }
+                // Nobody, or someone else, listens there now: the process
+                // that left the lock is gone and its id was reused.
+                Err(_) => {}
+            },
+            Some(_) => {}
+            None if written_lately(&file) => {
+                return Acquired::Unavailable(io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    "another instance is starting",
+                ));
+            }
+            None => {}
+        }
+
+        if let Err(error) = std::fs::remove_file(&file) {
+            if error.kind() != io::ErrorKind::NotFound {
+                return Acquired::Unavailable(error);
+            }
+        }
+    }
+
+    Acquired::Unavailable(io::Error::new(
+        io::ErrorKind::WouldBlock,
+        "the lock keeps being taken",
+    ))
+}
+
+/// Shows the owner of the lock the token, and checks it answers with its
+/// process id.
+fn greet(owner: &Owner) -> io::Result<TcpStream> {
+    let mut stream = TcpStream::connect_timeout(&owner.address, TIMEOUT)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    writeln!(stream, "{}", owner.token)?;
+
+    let mut answer = String::new();
+    io::BufReader::new(&stream).read_line(&mut answer)?;
+
+    if answer.trim_end() != owner.pid.to_string() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the socket does not belong to the lock",
+        ));
+    }
+
+    Ok(stream)
+}
+
+fn is_timeout(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+    )
+}
+
+/// Hands the files to attach to the instance already running.
+pub fn forward(mut stream: TcpStream, paths: &[PathBuf]) -> io::Result<()> {
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    for path in paths {
+        writeln!(stream, "{}", path.display())?;
+    }
+
+    stream.shutdown(std::net::Shutdown::Write)
+}
+
+/// Subscription recipe reading the paths sent by later launches.
+pub struct Listen {
+    file: PathBuf,
+    listener: TcpListener,
+    token: String,
+}
+
+impl<H: std::hash::Hasher, E> iced_native::subscription::Recipe<H, E> for Listen {
+    type Output = Vec<PathBuf>;
+
+    fn hash(&self, state: &mut H) {
+        std::any::TypeId::of::<Self>().hash(state);
+        self.file.hash(state);
+    }
+
+    fn stream(self: Box<Self>, _input: BoxStream<'static, E>) -> BoxStream<'static, Vec<PathBuf>> {
+        let listener = async_std::net::TcpListener::from(self.listener);
+
+        Box::pin(stream::unfold(
+            (listener, self.token),
+            |(listener, token)| async move {
+                let (stream, _) = listener.accept().await.ok()?;
+                let paths = receive(stream, &token).await;
+
+                Some((paths, (listener, token)))
+            },
+        ))
+    }
+}
+
+/// The paths sent on one connection, one per line after the token.
+async fn receive(stream: async_std::net::TcpStream, token: &str) -> Vec<PathBuf> {
+    match async_std::io::timeout(TIMEOUT, answer(stream, token)).await {
+        Ok(paths) => paths,
+        Err(error) => {
+            eprintln!("Cannot read what another launch sent: {}", error);
+            Vec::new()
+        }
+    }
+}
+
+async fn answer(stream: async_std::net::TcpStream, token: &str) -> io::Result<Vec<PathBuf>> {
+    let mut reader = async_std::io::BufReader::new(&stream);
+
+    let mut sent = String::new();
+    reader.read_line(&mut sent).await?;
+
+    if sent.trim_end() != token {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "the token is wrong",
+        ));
+    }
+
+    (&stream)
+        .write_all(format!("{}\n", std::process::id()).as_bytes())
+        .await?;
+
+    let mut contents = String::new();
+    reader.read_to_string(&mut contents).await?;
+
+    Ok(contents.lines().map(PathBuf::from).collect())
+}
+
+/// `todos.json.lock` next to `todos.json`.
+fn lock_file(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".lock");
+
+    path.with_file_name(name)
+}
+
+fn create(file: &Path, listener: &TcpListener, token: &str) -> io::Result<()> {
+    let owner = Owner {
+        pid: std::process::id(),
+        address: listener.local_addr()?,
+        token: token.to_string(),
+    };
+
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+
+    // Whoever reads the token may hand files to the app.
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut lock = options.open(file)?;
+
+    lock.write_all(serde_json::to_string(&owner)?.as_bytes())
+}
+
+fn read_owner(file: &Path) -> Option<Owner> {
+    serde_json::from_str(&std::fs::read_to_string(file).ok()?).ok()
+}
+
+/// Whether a process with this id exists, possibly under another user.
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+
+    // Signal 0 only checks that the process could be signalled.
+    let signalled = unsafe { libc::kill(pid, 0) } == 0;
+
+    signalled || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Without a cheap way to ask, the answer on the socket decides.
+#[cfg(not(unix))]
+fn is_running(_pid: u32) -> bool {
+    true
+}
+
+/// Whether the lock file may still be being written by its owner.
+fn written_lately(file: &Path) -> bool {
+    file.metadata()
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age < STARTING_GRACE)
+}
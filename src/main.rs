@@ -1,163 +1,1108 @@
 // use iced::alignment::{self, Alignment};
-use chrono::Local;
 use iced::button::{self, Button};
+use iced::pick_list::{self, PickList};
 use iced::scrollable::{self, Scrollable};
 use iced::text_input::{self, TextInput};
 use iced::{
-    Align, Application, Checkbox, Clipboard, Column, Command, Container, Element, Font, Length,
-    Row, Settings, Subscription, Text, Image, Space , Radio,
+    image, Align, Application, Checkbox, Clipboard, Column, Command, Container, Element, Font,
+    Image, Length, Radio, Row, Settings, Space, Subscription, Text,
+};
+use iced_native::keyboard::{self, KeyCode};
+use iced_native::{event, window, Event};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use todo_core::{
+    parse_description, parse_due, Attachment, Backup, BackupKind, Filter, Importance, ListId,
+    LoadError, Merge, SaveError, SavedState, Sort, TagFilter, TagMode, Target, Task, TaskId,
+    TaskStore,
 };
-use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
 
+mod files;
+mod history;
+mod icons;
+mod instance;
+mod recent;
+mod style;
+mod task;
+mod text_preview;
+mod thumbnails;
+mod watch;
+
+use files::{FileKind, Files};
+use history::History;
+use icons::Icons;
+use instance::{Access, Acquired};
+use recent::RecentFiles;
+use task::{TaskMessage, TaskState};
+use text_preview::TextPreviews;
+use thumbnails::{Thumbnail, Thumbnails};
+use watch::{OnDisk, Stamp};
+
+/// How long the "Task deleted" toast stays up.
+const TOAST_DURATION: std::time::Duration = std::time::Duration::from_secs(6);
+
+/// How often attached files are checked for having been moved or deleted.
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// How long changes are collected before they are written together.
+const SAVE_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// How long to wait before trying again after a save failed.
+const SAVE_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(10);
 
 pub fn main() -> iced::Result {
+    let arguments = match Arguments::parse() {
+        Ok(arguments) => arguments,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(2);
+        }
+    };
+    let path = arguments.file.unwrap_or_else(SavedState::discover);
+
+    let access = if arguments.read_only {
+        Access::ReadOnly(None)
+    } else {
+        match instance::acquire(&path) {
+            Acquired::Locked(lock) => Access::Locked(lock),
+            // The window that has the file takes over.
+            Acquired::Running(stream) => match instance::forward(stream, &arguments.attach) {
+                Ok(()) => return Ok(()),
+                Err(error) => Access::ReadOnly(Some(format!(
+                    "the window that has it open did not answer: {}",
+                    error
+                ))),
+            },
+            Acquired::Unavailable(error) => {
+                Access::ReadOnly(Some(format!("it cannot be locked: {}", error)))
+            }
+        }
+    };
+
     //    Todos::run(Settings::default())
     Todos::run(Settings {
         default_font: Some(include_bytes!("../fonts/NotoSansJP-Regular.otf")),
+        flags: Opening {
+            path,
+            access,
+            attach: arguments.attach,
+            closing: false,
+        },
+        // Unsaved changes are written before the window goes.
+        exit_on_close_request: false,
         ..Settings::default()
     })
 }
 
-#[derive(Debug,Copy,Clone,Serialize, Deserialize, Eq, PartialEq)]
-pub enum Importance {
-    Low,
-    Normal,
-    High,
+/// `[--file <path>] [--read-only] [<file to attach>...]`, with every path
+/// made absolute so it still names the same file in the recent files list
+/// or in another instance.
+#[derive(Debug, Default)]
+struct Arguments {
+    file: Option<PathBuf>,
+    read_only: bool,
+    attach: Vec<PathBuf>,
 }
 
-impl Importance {
-    fn all() -> [Importance; 3] {
-        [
-            Importance::Low,
-            Importance::Normal,
-            Importance::High,
-        ]
-    }
-}
+impl Arguments {
+    fn parse() -> Result<Arguments, String> {
+        let mut args = std::env::args_os().skip(1);
+        let mut arguments = Arguments::default();
+        let absolute = |path| std::path::absolute(path).map_err(|error| error.to_string());
 
+        while let Some(arg) = args.next() {
+            match arg.to_str() {
+                Some("--file") => {
+                    arguments.file = Some(absolute(args.next().ok_or("--file needs a path")?)?);
+                }
+                Some(arg) if arg.starts_with("--file=") => {
+                    arguments.file = Some(absolute(arg["--file=".len()..].into())?);
+                }
+                Some("--read-only") => arguments.read_only = true,
+                Some(arg) if arg.starts_with("--") => {
+                    return Err(format!("unexpected argument {}", arg));
+                }
+                _ => arguments.attach.push(absolute(arg)?),
+            }
+        }
 
-impl From<Importance> for String {
-    fn from(importance: Importance) -> String {
-        String::from(match importance {
-            Importance::Low => "Low",
-            Importance::Normal => "Normal",
-            Importance::High => "High",
-        })
+        Ok(arguments)
     }
 }
 
 #[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
 enum Todos {
-    Loading,
+    Loading(Opening),
     Loaded(State),
 }
 
+/// A todo file being read, and what comes with it once it is.
+#[derive(Debug, Default)]
+struct Opening {
+    path: PathBuf,
+    access: Access,
+    /// Files to attach, as if dropped on the window, once the tasks are in.
+    attach: Vec<PathBuf>,
+    /// The window was closed meanwhile; the app quits once the file being
+    /// left is written.
+    closing: bool,
+}
+
 #[derive(Debug, Default)]
 struct State {
+    /// The todo file the tasks are saved to.
+    path: PathBuf,
+    /// Nothing is saved while the file is read-only.
+    access: Access,
+    unlock_button: button::State,
+    on_disk: OnDisk,
+    /// Set when the file changed outside the app while there were unsaved
+    /// changes; nothing is saved until it is answered.
+    merge: Option<MergePrompt>,
+    recent: RecentFiles,
+    file_menu: FileMenu,
     scroll: scrollable::State,
     input: text_input::State,
     input_value: String,
     filter: Filter,
-    tasks: Vec<Task>,
+    sort: Sort,
+    importances: Vec<Importance>,
+    /// Whether checking the last subtask of a task completes the task.
+    auto_complete: bool,
+    tags: TagFilter,
+    store: TaskStore,
+    /// The list shown; `filter` is the one remembered for it.
+    current_list: ListId,
+    task_states: HashMap<TaskId, TaskState>,
     controls: Controls,
+    tag_sidebar: TagSidebar,
+    list_sidebar: ListSidebar,
+    /// Changes not written yet.
     dirty: bool,
+    /// A save is waiting for [`SAVE_DELAY`] to pass.
+    save_scheduled: bool,
     saving: bool,
-    file_path: PathBuf,
-    datetime: String,
+    /// The last save failed; its error is shown until one succeeds.
+    save_failed: bool,
+    /// The window was closed; the app quits once everything is written.
+    closing: bool,
+    /// Opens another file once the save under way is done, so it is not
+    /// written twice at once.
+    after_save: Option<Message>,
     filter_input_value: String,
     filter_input: text_input::State,
-    selected_importance: Option<Importance>
+    selected_importance: Option<Importance>,
+    due_input: text_input::State,
+    due_input_value: String,
+    /// The due date typed could not be read when adding a task.
+    due_unreadable: bool,
+    load_failure: Option<LoadFailure>,
+    retry_button: button::State,
+    start_fresh_button: button::State,
+    backup_panel: BackupPanel,
+    backup_count: usize,
+    history: History,
+    toast: Option<Toast>,
+    toasts_shown: u64,
+    /// Something that went wrong with the file, shown until dismissed.
+    error: Option<String>,
+    dismiss_error_button: button::State,
+    modifiers: keyboard::Modifiers,
+    /// The task Shift-dropped files are attached to.
+    drop_target: Option<TaskId>,
+    /// What was found at each attached path at the last check.
+    files: Files,
+    icons: Icons,
+    thumbnails: Thumbnails,
+    texts: TextPreviews,
+    /// Shown instead of the list while set.
+    preview: Option<Preview>,
+}
+
+/// A short notice with a shortcut to undo what it reports.
+#[derive(Debug)]
+struct Toast {
+    id: u64,
+    message: String,
+    /// The change it reports, as counted by [`History::recorded`]; once
+    /// another is made, Undo would undo that one instead.
+    change: u64,
+    undo_button: button::State,
+}
+
+/// Why the saved tasks could not be shown. While this is set nothing is
+/// saved, so the unreadable file is never replaced by an empty list.
+#[derive(Debug, Clone)]
+struct LoadFailure {
+    error: LoadError,
+    backup: Option<PathBuf>,
+}
+
+/// The unsaved tasks of a file being left.
+#[derive(Debug)]
+struct Leaving {
+    state: SavedState,
+    path: PathBuf,
+    /// The version of the file they were last in step with.
+    expected: Option<Stamp>,
+    /// Whether the merge prompt is open, so they cannot go into the file.
+    merging: bool,
+}
+
+impl State {
+    fn from_saved(saved: SavedState, path: PathBuf) -> State {
+        let store = TaskStore::with_lists(saved.lists, saved.tasks);
+        let current_list = match store.list(saved.current_list) {
+            Some(list) => list.id,
+            None => ListId::INBOX,
+        };
+        let task_states = store
+            .tasks()
+            .iter()
+            .map(|task| (task.id, TaskState::default()))
+            .collect();
+
+        State {
+            path,
+            recent: RecentFiles::load(),
+            input_value: saved.input_value,
+            filter: saved.filter,
+            sort: saved.sort,
+            importances: saved.importances,
+            auto_complete: saved.auto_complete,
+            tags: saved.tags,
+            store,
+            current_list,
+            task_states,
+            backup_count: saved.backup_count,
+            icons: Icons::load(),
+            ..State::default()
+        }
+    }
+
+    fn read_file(&self) -> Command<Message> {
+        Command::perform(watch::read(self.path.clone()), |(stamp, result)| {
+            Message::FileRead(stamp, result)
+        })
+    }
+
+    /// What is left to write to the current file before another one is
+    /// opened or the window closes, unless it could not be read in the first
+    /// place or is read-only.
+    fn leaving(&self) -> Option<Leaving> {
+        let unsaved = self.dirty || self.merge.is_some();
+
+        (unsaved && self.load_failure.is_none() && !self.access.is_read_only()).then(|| Leaving {
+            state: self.to_saved(),
+            path: self.path.clone(),
+            expected: self.on_disk.stamp,
+            merging: self.merge.is_some(),
+        })
+    }
+
+    fn can_save(&self) -> bool {
+        self.load_failure.is_none() && self.merge.is_none() && !self.access.is_read_only()
+    }
+
+    /// Writes what is left to save right away, as the window closes.
+    fn save_before_exit(&mut self) {
+        if let Some(leaving) = self.leaving() {
+            if put_away(leaving) {
+                self.dirty = false;
+            }
+        }
+    }
+
+    /// Locks the file at `path` before it replaces the current one, and
+    /// hands back the access to the current file to be given up once it is
+    /// written. The lock is kept when it is the same file.
+    fn lock_next(&mut self, path: &Path) -> (Access, Access) {
+        match std::mem::take(&mut self.access) {
+            access if access.guards(path) => (access, Access::default()),
+            current => (Access::open(path), current),
+        }
+    }
+
+    fn to_saved(&self) -> SavedState {
+        let mut lists = self.store.lists().to_vec();
+
+        if let Some(list) = lists.iter_mut().find(|list| list.id == self.current_list) {
+            list.filter = self.filter;
+        }
+
+        SavedState {
+            input_value: self.input_value.clone(),
+            filter: self.filter,
+            sort: self.sort,
+            importances: self.importances.clone(),
+            tags: self.tags.clone(),
+            lists,
+            current_list: self.current_list,
+            tasks: self.store.tasks().to_vec(),
+            auto_complete: self.auto_complete,
+            backup_count: self.backup_count,
+            ..SavedState::default()
+        }
+    }
+
+    /// Adds the typed task; links in its description become attachments.
+    fn add_task(&mut self, mut attachments: Vec<Attachment>) {
+        let due = parse_due(&self.due_input_value);
+        self.due_unreadable = due.is_none() && !self.due_input_value.trim().is_empty();
+
+        // Keep everything typed so far rather than silently dropping a due
+        // date that could not be understood. A dropped file still gets its
+        // task, without the due date.
+        if self.due_unreadable && attachments.is_empty() {
+            return;
+        }
+
+        let typed = parse_description(&self.input_value);
+
+        attachments.extend(typed.links);
+
+        self.history.record(&self.store);
+
+        let id = self.store.add(Task {
+            tags: typed.tags,
+            list: self.current_list,
+            ..Task::new(
+                typed.text,
+                attachments,
+                self.selected_importance.unwrap_or_default(),
+                due,
+            )
+        });
+        self.task_states.insert(id, TaskState::default());
+        self.input_value.clear();
+
+        if !self.due_unreadable {
+            self.due_input_value.clear();
+        }
+    }
+
+    /// Attaches a dropped file to the task being edited, or to the drop
+    /// target when Shift is held; otherwise the file starts a new task.
+    fn drop_file(&mut self, path: PathBuf) {
+        if let Some(task_state) = self
+            .task_states
+            .values_mut()
+            .find(|task_state| task_state.is_editing())
+        {
+            task_state.edit_field(TaskMessage::FileChosen(Some(path)));
+            return;
+        }
+
+        let target = self.drop_target.filter(|id| self.store.get(*id).is_some());
+
+        match target {
+            Some(id) if self.modifiers.shift => {
+                self.history.record(&self.store);
+                self.store.attach(id, Attachment::new(path));
+            }
+            _ => self.add_task(vec![Attachment::new(path)]),
+        }
+    }
+
+    /// Swaps in a task list from the undo history.
+    fn replace_store(&mut self, store: TaskStore) {
+        self.store = store;
+
+        let ids = self
+            .store
+            .tasks()
+            .iter()
+            .map(|task| task.id)
+            .collect::<Vec<_>>();
+
+        self.task_states.retain(|id, _| ids.contains(id));
+
+        for id in ids {
+            self.task_states.entry(id).or_default();
+        }
+
+        if self.store.list(self.current_list).is_none() {
+            self.current_list = ListId::INBOX;
+        }
+    }
+
+    /// Shows another list, with the filter last chosen for it.
+    fn select_list(&mut self, id: ListId) {
+        let Some(filter) = self.store.list(id).map(|list| list.filter) else {
+            return;
+        };
+
+        self.store.set_list_filter(self.current_list, self.filter);
+        self.current_list = id;
+        self.filter = filter;
+        self.list_sidebar.renaming = None;
+    }
+
+    fn show_toast(&mut self, message: &str) -> Command<Message> {
+        self.toasts_shown += 1;
+
+        let id = self.toasts_shown;
+
+        self.toast = Some(Toast {
+            id,
+            message: String::from(message),
+            change: self.history.recorded(),
+            undo_button: button::State::new(),
+        });
+
+        Command::perform(async_std::task::sleep(TOAST_DURATION), move |_| {
+            Message::ToastExpired(id)
+        })
+    }
+
+    /// Swaps a task with the visible task above or below it.
+    fn move_task(&mut self, id: TaskId, up: bool) {
+        let visible = self
+            .store
+            .sorted(
+                Some(self.current_list),
+                self.filter,
+                &self.filter_input_value,
+                &self.importances,
+                &self.tags,
+                self.sort,
+            )
+            .iter()
+            .map(|task| task.id)
+            .collect::<Vec<_>>();
+
+        let neighbour = visible
+            .iter()
+            .position(|visible| *visible == id)
+            .and_then(|position| {
+                if up {
+                    position.checked_sub(1)
+                } else {
+                    Some(position + 1)
+                }
+            })
+            .and_then(|position| visible.get(position));
+
+        if let Some(neighbour) = neighbour {
+            self.history.record(&self.store);
+            self.store.swap(id, *neighbour);
+        }
+    }
+
+    /// Looks for attached files that no longer exist and sniffs the type of
+    /// the others, off the UI thread.
+    fn check_attachments(&self) -> Command<Message> {
+        let paths = self
+            .store
+            .tasks()
+            .iter()
+            .flat_map(|task| &task.attachments)
+            .filter_map(|attachment| attachment.path())
+            .map(PathBuf::from)
+            .collect();
+
+        Command::perform(files::inspect(paths), Message::AttachmentsChecked)
+    }
+
+    fn update_task(&mut self, id: TaskId, message: TaskMessage) -> Command<Message> {
+        match message {
+            TaskMessage::Completed(completed) => {
+                self.history.record(&self.store);
+                self.store.complete(id, completed);
+            }
+            TaskMessage::Edit => {
+                if let (Some(task), Some(task_state)) =
+                    (self.store.get(id), self.task_states.get_mut(&id))
+                {
+                    task_state.edit(task, self.store.lists());
+                }
+            }
+            TaskMessage::BrowseFile => {
+                return Command::perform(browse_file(), move |path| {
+                    Message::TaskMessage(id, TaskMessage::FileChosen(path))
+                });
+            }
+            TaskMessage::DescriptionEdited(_)
+            | TaskMessage::ImportanceEdited(_)
+            | TaskMessage::FileChosen(_)
+            | TaskMessage::RemoveAttachment(_)
+            | TaskMessage::RemoveTag(_)
+            | TaskMessage::ListEdited(_)
+            | TaskMessage::DueEdited(_)
+            | TaskMessage::CreatedEdited(_) => {
+                if let Some(task_state) = self.task_states.get_mut(&id) {
+                    task_state.edit_field(message);
+                }
+            }
+            TaskMessage::FinishEdition => {
+                let Some(task_state) = self.task_states.get_mut(&id) else {
+                    return Command::none();
+                };
+
+                // Deleted meanwhile, e.g. by another instance.
+                let Some(current) = self.store.get(id) else {
+                    task_state.finish_edition();
+                    return Command::none();
+                };
+
+                if let Some(edited) = task_state.edited(current) {
+                    task_state.finish_edition();
+
+                    if current != &edited {
+                        self.history.record(&self.store);
+                        self.store.replace(edited);
+                    }
+                }
+            }
+            TaskMessage::CancelEdition => {
+                if let Some(task_state) = self.task_states.get_mut(&id) {
+                    task_state.finish_edition();
+                }
+            }
+            TaskMessage::MoveUp => self.move_task(id, true),
+            TaskMessage::MoveDown => self.move_task(id, false),
+            TaskMessage::Delete => {
+                self.history.record(&self.store);
+                self.store.delete(id);
+                self.task_states.remove(&id);
+            }
+            TaskMessage::StartProcess(target) => {
+                let result = match &target {
+                    Target::LocalFile { path } if !path.exists() => {
+                        self.files.insert(path.clone(), FileKind::Missing);
+                        Err(format!("ファイルが見つかりません: {}", path.display()))
+                    }
+                    _ => open::that(target.location()).map_err(|error| {
+                        format!(
+                            "開けませんでした: {} ({})",
+                            target.location().to_string_lossy(),
+                            error
+                        )
+                    }),
+                };
+
+                if let Some(task_state) = self.task_states.get_mut(&id) {
+                    task_state.set_error(result.err());
+                }
+            }
+            TaskMessage::Locate(path) => {
+                return Command::perform(locate_file(path), move |(old_path, new_path)| {
+                    Message::TaskMessage(id, TaskMessage::Located(old_path, new_path))
+                });
+            }
+            TaskMessage::Located(old_path, Some(new_path)) => {
+                self.history.record(&self.store);
+                self.store.relocate(&old_path, &new_path);
+
+                if let Some(task_state) = self.task_states.get_mut(&id) {
+                    task_state.set_error(None);
+                }
+
+                return self.check_attachments();
+            }
+            TaskMessage::Located(_, None) => {}
+            TaskMessage::Preview(path) => {
+                self.preview = Some(Preview::new(path));
+            }
+            TaskMessage::ToggleText(index, path) => {
+                let shown = self
+                    .task_states
+                    .get_mut(&id)
+                    .is_some_and(|task_state| task_state.toggle_text(index));
+
+                // Read again each time, the file may have been edited since.
+                if shown {
+                    self.texts.remove(&path);
+
+                    return Command::perform(text_preview::load(path), |(path, preview)| {
+                        Message::TextLoaded(path, preview)
+                    });
+                }
+            }
+            TaskMessage::SubtaskCompleted(index, completed) => {
+                self.history.record(&self.store);
+                self.store
+                    .complete_subtask(id, index, completed, self.auto_complete);
+            }
+            TaskMessage::SubtaskInputChanged(value) => {
+                if let Some(task_state) = self.task_states.get_mut(&id) {
+                    task_state.set_subtask_value(value);
+                }
+            }
+            TaskMessage::AddSubtask => {
+                let description = self
+                    .task_states
+                    .get_mut(&id)
+                    .and_then(|task_state| task_state.take_subtask());
+
+                if let Some(description) = description {
+                    self.history.record(&self.store);
+                    self.store.add_subtask(id, description);
+                }
+            }
+            TaskMessage::DeleteSubtask(index) => {
+                self.history.record(&self.store);
+                self.store.delete_subtask(id, index);
+            }
+            TaskMessage::TagSelected(tag) => self.tags.toggle(&tag),
+            TaskMessage::ToggleDropTarget => {
+                self.drop_target = (self.drop_target != Some(id)).then_some(id);
+            }
+        }
+
+        Command::none()
+    }
 }
 
 #[derive(Debug, Clone)]
+#[allow(clippy::enum_variant_names)]
 enum Message {
-    Loaded(Result<SavedState, LoadError>),
-    Saved(Result<(), SaveError>),
+    Loaded(Result<SavedState, LoadFailure>),
+    /// What was written, `None` if the file had changed on disk meanwhile.
+    Saved(Result<Option<OnDisk>, SaveError>),
+    /// The changes have been collected long enough to be written.
+    SaveDue,
+    CloseRequested,
+    CheckFile,
+    /// Files sent to attach by a later launch of the app.
+    Forwarded(Vec<PathBuf>),
+    /// Tries again to lock a file opened read-only.
+    Unlock,
+    FileRead(Option<Stamp>, Result<SavedState, LoadError>),
+    ConflictChoice(usize, bool),
+    ApplyMerge,
+    KeepMine,
+    TakeTheirs,
     InputChanged(String),
     CreateTask,
     FilterChanged(Filter),
-    TaskMessage(usize, TaskMessage),
+    SortChanged(Sort),
+    ImportanceFilterToggled(Importance),
+    AutoCompleteToggled(bool),
+    TagToggled(String),
+    ListSelected(ListId),
+    NewListChanged(String),
+    CreateList,
+    /// Starts renaming the current list.
+    RenameList,
+    ListNameChanged(String),
+    FinishRenaming,
+    ArchiveList(ListId, bool),
+    DeleteList(ListId),
+    ShowArchivedToggled(bool),
+    ToggleFileMenu,
+    NewFile,
+    OpenFile,
+    SaveAs,
+    /// Where to start a new file, `None` if the dialog was dismissed.
+    NewFileChosen(Option<PathBuf>),
+    OpenFileChosen(Option<PathBuf>),
+    OpenRecent(PathBuf),
+    SaveAsChosen(Option<PathBuf>),
+    DismissError,
+    TagModeChanged(TagMode),
+    ClearTags,
+    TaskMessage(TaskId, TaskMessage),
     Dropped(iced_native::Event),
+    ModifiersChanged(keyboard::Modifiers),
+    CheckAttachments,
+    AttachmentsChecked(Files),
     FilterTextChanged(String),
     ImportanceChanged(Importance),
+    DueChanged(String),
+    RetryLoad,
+    StartFresh,
+    ToggleBackups,
+    BackupsListed(Vec<Backup>),
+    RestoreBackup(PathBuf),
+    Restored(Result<SavedState, LoadError>),
+    Undo,
+    Redo,
+    /// Closes the preview, or else cancels every edition in progress.
+    Escape,
+    ThumbnailGenerated(PathBuf, Option<Stamp>, Option<PathBuf>),
+    ThumbnailsPruned,
+    TextLoaded(PathBuf, Result<text_preview::TextPreview, String>),
+    OpenPreviewed,
+    ClosePreview,
+    ToastExpired(u64),
+}
+
+impl Message {
+    /// Whether handling the message may change what is saved, and so is
+    /// worth comparing the tasks before and after for. What is read from
+    /// the file is on disk already, and the text being typed into the new
+    /// task box is only saved along with a real change.
+    fn may_change_saved(&self) -> bool {
+        match self {
+            Message::Dropped(event) => {
+                matches!(event, Event::Window(window::Event::FileDropped(_)))
+            }
+            Message::Saved(_)
+            | Message::SaveDue
+            | Message::CloseRequested
+            | Message::CheckFile
+            | Message::FileRead(..)
+            | Message::ModifiersChanged(_)
+            | Message::CheckAttachments
+            | Message::AttachmentsChecked(_)
+            | Message::BackupsListed(_)
+            | Message::ThumbnailGenerated(..)
+            | Message::ThumbnailsPruned
+            | Message::TextLoaded(..)
+            | Message::ToastExpired(_)
+            | Message::InputChanged(_) => false,
+            _ => true,
+        }
+    }
 }
 
 impl Application for Todos {
     type Executor = iced::executor::Default;
     type Message = Message;
-    type Flags = ();
+    type Flags = Opening;
 
-    fn new(_flags: ()) -> (Todos, Command<Message>) {
-        (
-            Todos::Loading,
-            Command::perform(SavedState::load(), Message::Loaded),
-        )
+    fn new(opening: Opening) -> (Todos, Command<Message>) {
+        let command = Command::perform(
+            load(opening.path.clone(), opening.access.is_read_only()),
+            Message::Loaded,
+        );
+
+        (Todos::Loading(opening), command)
     }
 
     fn title(&self) -> String {
-        let dirty = match self {
-            Todos::Loading => false,
-            Todos::Loaded(state) => state.dirty,
+        let (path, access, dirty) = match self {
+            Todos::Loading(opening) => (&opening.path, &opening.access, false),
+            Todos::Loaded(state) => (&state.path, &state.access, state.dirty),
         };
 
-        format!("Todos{} - Iced", if dirty { "*" } else { "" })
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        let dir = path.parent().unwrap_or(Path::new("")).display();
+
+        format!(
+            "{}{} ({}){} - Todos",
+            name,
+            if dirty { "*" } else { "" },
+            dir,
+            if access.is_read_only() {
+                " [read-only]"
+            } else {
+                ""
+            }
+        )
     }
 
     fn subscription(&self) -> Subscription<Self::Message> {
-        iced_native::subscription::events().map(Message::Dropped)
+        // Shortcuts are taken even while a text input has the focus, since
+        // inputs swallow every key press.
+        let events = iced_native::subscription::events_with(|event, status| match event {
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code: KeyCode::Z,
+                modifiers,
+            }) if modifiers.is_command_pressed() => Some(if modifiers.shift {
+                Message::Redo
+            } else {
+                Message::Undo
+            }),
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code: KeyCode::Y,
+                modifiers,
+            }) if modifiers.is_command_pressed() => Some(Message::Redo),
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code: KeyCode::Escape,
+                ..
+            }) => Some(Message::Escape),
+            Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                Some(Message::ModifiersChanged(modifiers))
+            }
+            Event::Window(window::Event::CloseRequested) => Some(Message::CloseRequested),
+            event if status == event::Status::Ignored => Some(Message::Dropped(event)),
+            _ => None,
+        });
+
+        let mut subscriptions = vec![
+            events,
+            iced::time::every(CHECK_INTERVAL).map(|_| Message::CheckAttachments),
+            iced::time::every(watch::INTERVAL).map(|_| Message::CheckFile),
+        ];
+
+        if let Todos::Loaded(state) = self {
+            if let Some(listen) = state.access.listen() {
+                subscriptions.push(Subscription::from_recipe(listen).map(Message::Forwarded));
+            }
+        }
+
+        Subscription::batch(subscriptions)
     }
 
     fn update(&mut self, message: Message, _clipboard: &mut Clipboard) -> Command<Message> {
         match self {
-            Todos::Loading => {
+            Todos::Loading(opening) => {
+                let path = &opening.path;
+
                 match message {
                     Message::Loaded(Ok(state)) => {
+                        let mut state = State {
+                            access: std::mem::take(&mut opening.access),
+                            closing: opening.closing,
+                            ..State::from_saved(state, path.clone())
+                        };
+                        state.recent.push(path);
+                        state.on_disk = OnDisk::new(Stamp::of(path), &state.to_saved());
+                        let attach = std::mem::take(&mut opening.attach);
+                        let command = Command::batch(vec![
+                            state.check_attachments(),
+                            Command::perform(async { attach }, Message::Forwarded),
+                        ]);
+
+                        *self = Todos::Loaded(state);
+
+                        return command;
+                    }
+                    Message::Loaded(Err(failure)) => {
                         *self = Todos::Loaded(State {
-                            input_value: state.input_value,
-                            filter: state.filter,
-                            tasks: state.tasks,
-                            ..State::default()
+                            access: std::mem::take(&mut opening.access),
+                            closing: opening.closing,
+                            load_failure: Some(failure),
+                            ..State::from_saved(SavedState::default(), path.clone())
                         });
                     }
-                    Message::Loaded(Err(_)) => {
-                        *self = Todos::Loaded(State::default());
-                    }
+                    Message::CloseRequested => opening.closing = true,
                     _ => {}
                 }
 
                 Command::none()
             }
             Todos::Loaded(state) => {
-                let mut saved = false;
+                let before = message.may_change_saved().then(|| state.to_saved());
+                let mut command = Command::none();
 
                 match message {
+                    Message::RetryLoad => {
+                        let path = state.path.clone();
+                        let read_only = state.access.is_read_only();
+                        *self = Todos::Loading(Opening {
+                            path: path.clone(),
+                            access: std::mem::take(&mut state.access),
+                            attach: Vec::new(),
+                            closing: false,
+                        });
+
+                        return Command::perform(load(path, read_only), Message::Loaded);
+                    }
+                    Message::Unlock if state.access.is_read_only() => {
+                        state.access = Access::open(&state.path);
+                    }
+                    Message::Forwarded(paths) => {
+                        for path in paths {
+                            state.drop_file(path);
+                        }
+                    }
+                    Message::ToggleFileMenu => state.file_menu.open = !state.file_menu.open,
+                    Message::NewFile => {
+                        return Command::perform(
+                            choose_file_to_save("New todo file", state.path.clone()),
+                            Message::NewFileChosen,
+                        );
+                    }
+                    Message::OpenFile => {
+                        return Command::perform(
+                            choose_file_to_open(state.path.clone()),
+                            Message::OpenFileChosen,
+                        );
+                    }
+                    Message::SaveAs => {
+                        return Command::perform(
+                            choose_file_to_save("Save the tasks as", state.path.clone()),
+                            Message::SaveAsChosen,
+                        );
+                    }
+                    Message::NewFileChosen(Some(_))
+                    | Message::OpenFileChosen(Some(_))
+                    | Message::OpenRecent(_)
+                    | Message::SaveAsChosen(Some(_))
+                        if state.saving =>
+                    {
+                        state.after_save = Some(message);
+                    }
+                    // Leaving would write over the changes made outside the
+                    // app before the user got to keep them.
+                    Message::NewFileChosen(Some(_))
+                    | Message::OpenFileChosen(Some(_))
+                    | Message::OpenRecent(_)
+                    | Message::SaveAsChosen(Some(_))
+                        if state.merge.is_some() =>
+                    {
+                        state.error = Some(String::from(
+                            "The file was changed outside the app: choose which changes to keep first.",
+                        ));
+                    }
+                    Message::NewFileChosen(Some(path)) => {
+                        let leaving = state.leaving();
+                        let (access, left) = state.lock_next(&path);
+
+                        // Starting over would wipe the tasks of the window
+                        // that has the file.
+                        if let Access::ReadOnly(reason) = access {
+                            state.access = left;
+                            state.error = Some(format!(
+                                "Cannot start a new file at {}: {}.",
+                                path.display(),
+                                reason.unwrap_or_else(|| String::from("it is read-only"))
+                            ));
+
+                            return Command::none();
+                        }
 
+                        *self = Todos::Loading(Opening {
+                            path: path.clone(),
+                            access,
+                            attach: Vec::new(),
+                            closing: false,
+                        });
+
+                        return Command::perform(create(leaving, left, path), Message::Loaded);
+                    }
+                    Message::OpenFileChosen(Some(path)) | Message::OpenRecent(path) => {
+                        let leaving = state.leaving();
+                        let (access, left) = state.lock_next(&path);
+                        let read_only = access.is_read_only();
+                        *self = Todos::Loading(Opening {
+                            path: path.clone(),
+                            access,
+                            attach: Vec::new(),
+                            closing: false,
+                        });
 
-                    Message::CreateTask => {
-                        if !state.input_value.is_empty() {
-                            state.file_path = PathBuf::new();
-                            state
-                                .tasks
-                                .push(Task::new(
-                                    state.input_value.clone(),
-                                    state.file_path.clone(),
-                                    state.datetime.clone(),
-                                    Importance::from(state.selected_importance.unwrap_or(Importance::Normal)),
-                                    ));
-                            state.input_value.clear();
+                        return Command::perform(
+                            switch(leaving, left, path, read_only),
+                            Message::Loaded,
+                        );
+                    }
+                    Message::SaveAsChosen(Some(path)) => {
+                        let (access, current) = state.lock_next(&path);
+                        // The tasks go to the new file whatever it held, so
+                        // its stamp is not checked.
+                        let saved = state.to_saved();
+                        let written = match &access {
+                            Access::ReadOnly(reason) => Err(reason
+                                .clone()
+                                .unwrap_or_else(|| String::from("it is read-only"))),
+                            Access::Locked(_) => {
+                                saved.save(&path).map_err(|error| error.to_string())
+                            }
+                        };
+
+                        match written {
+                            Ok(()) => {
+                                state.access = access;
+                                state.on_disk = OnDisk::new(Stamp::of(&path), &saved);
+                                state.dirty = false;
+                                state.recent.push(&path);
+                                state.path = path;
+                                state.file_menu.open = false;
+                            }
+                            Err(reason) => {
+                                // Still the lock of the current file when
+                                // saving it as itself failed.
+                                state.access = if access.guards(&state.path) {
+                                    access
+                                } else {
+                                    current
+                                };
+                                state.error =
+                                    Some(format!("Cannot save as {}: {}.", path.display(), reason));
+                            }
+                        }
+                    }
+                    Message::DismissError => state.error = None,
+                    Message::StartFresh => {
+                        state.load_failure = None;
+                    }
+                    Message::ToggleBackups => match state.backup_panel.entries.take() {
+                        Some(_) => {}
+                        None => {
+                            command = Command::perform(
+                                list_backups(state.path.clone()),
+                                Message::BackupsListed,
+                            )
+                        }
+                    },
+                    Message::BackupsListed(backups) => {
+                        state.backup_panel.show(backups);
+                    }
+                    Message::RestoreBackup(path) => {
+                        command = Command::perform(restore(path), Message::Restored);
+                    }
+                    Message::Restored(Ok(restored)) => {
+                        let mut history = std::mem::take(&mut state.history);
+                        history.record(&state.store);
+
+                        // A save may still be in flight; remember it so the
+                        // restored tasks are written once it completes.
+                        *state = State {
+                            access: std::mem::take(&mut state.access),
+                            save_scheduled: state.save_scheduled,
+                            saving: state.saving,
+                            save_failed: state.save_failed,
+                            error: state.error.take(),
+                            closing: state.closing,
+                            after_save: state.after_save.take(),
+                            on_disk: std::mem::take(&mut state.on_disk),
+                            history,
+                            ..State::from_saved(restored, state.path.clone())
+                        };
+                    }
+                    Message::Undo => {
+                        if let Some(store) = state.history.undo(&state.store) {
+                            state.replace_store(store);
+                        }
+                        state.toast = None;
+                    }
+                    Message::Redo => {
+                        if let Some(store) = state.history.redo(&state.store) {
+                            state.replace_store(store);
+                        }
+                    }
+                    Message::Escape if state.preview.is_some() => {
+                        state.preview = None;
+                    }
+                    Message::Escape => {
+                        for task_state in state.task_states.values_mut() {
+                            if task_state.is_editing() {
+                                task_state.finish_edition();
+                            }
                         }
                     }
+                    Message::ToastExpired(id)
+                        if state.toast.as_ref().is_some_and(|toast| toast.id == id) =>
+                    {
+                        state.toast = None;
+                    }
+                    Message::Restored(Err(error)) => {
+                        state.backup_panel.error = Some(error);
+                    }
 
-                    Message::FilterTextChanged(value) => {
-                        state.filter_input_value = value
+                    Message::CreateTask if !state.input_value.is_empty() => {
+                        state.add_task(Vec::new());
                     }
 
+                    Message::FilterTextChanged(value) => state.filter_input_value = value,
+
                     Message::ImportanceChanged(importance) => {
                         state.selected_importance = Some(importance);
                     }
 
-                    
+                    Message::DueChanged(value) => {
+                        state.due_input_value = value;
+                        state.due_unreadable = false;
+                    }
+
                     Message::InputChanged(value) => {
                         state.input_value = value;
                     }
@@ -165,126 +1110,554 @@ impl Application for Todos {
                     Message::FilterChanged(filter) => {
                         state.filter = filter;
                     }
-                    Message::TaskMessage(i, TaskMessage::Delete) => {
-                        state.tasks.remove(i);
+                    Message::SortChanged(sort) => {
+                        state.sort = sort;
+                    }
+                    Message::AutoCompleteToggled(auto_complete) => {
+                        state.auto_complete = auto_complete;
                     }
-                    Message::TaskMessage(i, task_message) => {
-                        if let Some(task) = state.tasks.get_mut(i) {
-                            task.update(task_message);
+                    Message::TagToggled(tag) => state.tags.toggle(&tag),
+                    Message::ListSelected(id) => state.select_list(id),
+                    Message::NewListChanged(value) => state.list_sidebar.new_list_value = value,
+                    Message::CreateList => {
+                        let name = std::mem::take(&mut state.list_sidebar.new_list_value);
+                        let name = name.trim();
+
+                        if !name.is_empty() {
+                            state.history.record(&state.store);
+                            let id = state.store.add_list(name.to_string());
+                            state.select_list(id);
                         }
                     }
-                    Message::Saved(_) => {
-                        state.saving = false;
-                        saved = true;
-                    }
-                    Message::Dropped(event) => {
-                        if let iced_native::event::Event::Window(we) = event {
-                            if let iced_native::window::Event::FileDropped(path) = we {
-                                state.datetime = Local::now().format(" Added %Y/%m/%d %H:%M").to_string();
-                                state.file_path = path;
-                                state.tasks.push(Task::new(
-                                    state.input_value.clone(),
-                                    state.file_path.clone(),
-                                        state.datetime.clone(),
-                                        Importance::from(state.selected_importance.unwrap_or(Importance::Normal)),
+                    Message::RenameList => {
+                        state.list_sidebar.renaming = state
+                            .store
+                            .list(state.current_list)
+                            .map(|list| (text_input::State::focused(), list.name.clone()));
+                    }
+                    Message::ListNameChanged(value) => {
+                        if let Some((_, name)) = &mut state.list_sidebar.renaming {
+                            *name = value;
+                        }
+                    }
+                    Message::FinishRenaming => {
+                        if let Some((_, name)) = state.list_sidebar.renaming.take() {
+                            let name = name.trim();
+                            let changed = state
+                                .store
+                                .list(state.current_list)
+                                .is_some_and(|list| list.name != name);
+
+                            if !name.is_empty() && changed {
+                                state.history.record(&state.store);
+                                state
+                                    .store
+                                    .rename_list(state.current_list, name.to_string());
+                            }
+                        }
+                    }
+                    Message::ArchiveList(id, archived) => {
+                        state.history.record(&state.store);
+                        state.store.archive_list(id, archived);
+
+                        if archived && id == state.current_list && !state.list_sidebar.show_archived
+                        {
+                            state.select_list(ListId::INBOX);
+                        }
+                    }
+                    Message::DeleteList(id) => {
+                        if id == state.current_list {
+                            state.select_list(ListId::INBOX);
+                        }
+
+                        state.history.record(&state.store);
+
+                        if let Some((_, tasks)) = state.store.delete_list(id) {
+                            for task in tasks {
+                                state.task_states.remove(&task.id);
+                            }
+
+                            command = state.show_toast("List deleted");
+                        }
+                    }
+                    Message::ShowArchivedToggled(show_archived) => {
+                        state.list_sidebar.show_archived = show_archived;
+                    }
+                    Message::TagModeChanged(mode) => state.tags.mode = mode,
+                    Message::ClearTags => state.tags.tags.clear(),
+                    Message::ImportanceFilterToggled(importance) => {
+                        let importances = &mut state.importances;
+
+                        match importances.iter().position(|shown| *shown == importance) {
+                            Some(index) => {
+                                importances.remove(index);
+                            }
+                            None => {
+                                importances.push(importance);
+                                importances.sort();
+                            }
+                        }
+                    }
+                    Message::TaskMessage(id, task_message) => {
+                        let deleted = matches!(task_message, TaskMessage::Delete);
+
+                        command = state.update_task(id, task_message);
+
+                        if deleted {
+                            command = state.show_toast("Task deleted");
+                        }
+                    }
+                    Message::Saved(result) => {
+                        match result {
+                            Ok(Some(on_disk)) => {
+                                state.on_disk = on_disk;
+
+                                if std::mem::take(&mut state.save_failed) {
+                                    state.error = None;
+                                }
+                            }
+                            // Someone else wrote the file; find out what
+                            // changed before saving again.
+                            Ok(None) => {
+                                state.dirty = true;
+                                command = state.read_file();
+                            }
+                            Err(error) => {
+                                state.dirty = true;
+                                state.save_failed = true;
+                                state.error = Some(format!(
+                                    "The tasks could not be saved to {}: {}. Trying again shortly.",
+                                    state.path.display(),
+                                    error
                                 ));
-                                state.input_value.clear();
+
+                                // Rather than as soon as the usual delay is over.
+                                if !state.save_scheduled {
+                                    state.save_scheduled = true;
+                                    command = Command::perform(
+                                        async_std::task::sleep(SAVE_RETRY_DELAY),
+                                        |_| Message::SaveDue,
+                                    );
+                                }
                             }
                         }
+
+                        state.saving = false;
+
+                        if state.closing {
+                            state.save_before_exit();
+                        } else if let Some(message) = state.after_save.take() {
+                            command = Command::batch(vec![
+                                command,
+                                Command::perform(async { message }, |message| message),
+                            ]);
+                        }
+                    }
+                    Message::SaveDue => {
+                        state.save_scheduled = false;
+
+                        if state.dirty && !state.saving && state.can_save() {
+                            state.dirty = false;
+                            state.saving = true;
+
+                            command = Command::perform(
+                                save(state.to_saved(), state.path.clone(), state.on_disk.stamp),
+                                Message::Saved,
+                            );
+                        }
+                    }
+                    Message::CloseRequested => {
+                        state.closing = true;
+
+                        // Otherwise once the save under way is done.
+                        if !state.saving {
+                            state.save_before_exit();
+                        }
+                    }
+                    Message::CheckFile
+                        if !state.saving
+                            && state.merge.is_none()
+                            && state.load_failure.is_none()
+                            && Stamp::of(&state.path) != state.on_disk.stamp =>
+                    {
+                        command = state.read_file();
+                    }
+                    // The file may have changed again by the time this save
+                    // ends, it will be looked at then.
+                    Message::FileRead(..) if state.saving => {}
+                    Message::FileRead(stamp, Err(error)) => {
+                        // Possibly written halfway; the rest of it will
+                        // change the stamp again.
+                        eprintln!("Ignoring the change to {}: {}", state.path.display(), error);
+                        state.on_disk.stamp = stamp;
+                    }
+                    Message::FileRead(stamp, Ok(remote)) => {
+                        let remote = TaskStore::with_lists(remote.lists, remote.tasks);
+
+                        if state.on_disk.lists == remote.lists()
+                            && state.on_disk.tasks == remote.tasks()
+                        {
+                            state.on_disk.stamp = stamp;
+                        } else if state.on_disk.matches(&state.to_saved()) {
+                            state.history.record(&state.store);
+                            state.replace_store(remote);
+                            state.on_disk = OnDisk::new(stamp, &state.to_saved());
+                            command =
+                                state.show_toast("Reloaded the tasks changed outside the app");
+                        } else {
+                            state.merge = Some(MergePrompt::new(
+                                &state.on_disk,
+                                state.to_saved(),
+                                remote,
+                                stamp,
+                            ));
+                        }
+                    }
+                    Message::ConflictChoice(index, take_remote) => {
+                        if let Some(choice) = state
+                            .merge
+                            .as_mut()
+                            .and_then(|merge| merge.take_remote.get_mut(index))
+                        {
+                            *choice = take_remote;
+                        }
+                    }
+                    Message::ApplyMerge => {
+                        if let Some(merge) = state.merge.take() {
+                            let tasks = merge.merge.resolve(&merge.take_remote);
+
+                            state.history.record(&state.store);
+                            state.replace_store(TaskStore::with_lists(merge.merge.lists, tasks));
+                            state.on_disk = merge.remote;
+                        }
+                    }
+                    Message::KeepMine => {
+                        if let Some(merge) = state.merge.take() {
+                            state.on_disk = merge.remote;
+                        }
+                    }
+                    Message::TakeTheirs => {
+                        if let Some(merge) = state.merge.take() {
+                            state.history.record(&state.store);
+                            state.replace_store(TaskStore::with_lists(
+                                merge.remote.lists.clone(),
+                                merge.remote.tasks.clone(),
+                            ));
+                            state.on_disk = merge.remote;
+                        }
+                    }
+                    Message::Dropped(iced_native::Event::Window(
+                        iced_native::window::Event::FileDropped(path),
+                    )) => {
+                        state.drop_file(path);
+                    }
+                    Message::ModifiersChanged(modifiers) => {
+                        state.modifiers = modifiers;
+                    }
+                    Message::CheckAttachments => {
+                        command = state.check_attachments();
+                    }
+                    Message::AttachmentsChecked(files) => {
+                        let images = files
+                            .iter()
+                            .filter(|(_, kind)| {
+                                matches!(kind, FileKind::File(Some(mime_type)) if thumbnails::is_image(mime_type))
+                            })
+                            .map(|(path, _)| path.clone())
+                            .collect::<Vec<_>>();
+
+                        // Only the images changed since their thumbnail was
+                        // made.
+                        let mut commands = images
+                            .iter()
+                            .filter(|path| {
+                                state
+                                    .thumbnails
+                                    .get(*path)
+                                    .is_none_or(|thumbnail| thumbnail.stamp != Stamp::of(path))
+                            })
+                            .map(|path| {
+                                Command::perform(
+                                    thumbnails::generate(path.clone()),
+                                    |(path, stamp, thumbnail)| {
+                                        Message::ThumbnailGenerated(path, stamp, thumbnail)
+                                    },
+                                )
+                            })
+                            .collect::<Vec<_>>();
+
+                        // The cache is tidied up whenever thumbnails are made.
+                        if !commands.is_empty() {
+                            commands.push(Command::perform(thumbnails::prune(images), |_| {
+                                Message::ThumbnailsPruned
+                            }));
+                        }
+
+                        command = Command::batch(commands);
+
+                        state.thumbnails.retain(|path, _| files.contains_key(path));
+                        state.files = files;
+                    }
+                    Message::ThumbnailGenerated(path, stamp, thumbnail) => {
+                        state.thumbnails.insert(
+                            path,
+                            Thumbnail {
+                                stamp,
+                                handle: thumbnail.map(image::Handle::from_path),
+                            },
+                        );
+                    }
+                    Message::ThumbnailsPruned => {}
+                    Message::TextLoaded(path, preview) => {
+                        state.texts.insert(path, preview);
+                    }
+                    Message::OpenPreviewed => {
+                        if let Some(preview) = &mut state.preview {
+                            preview.error = open::that(&preview.path)
+                                .err()
+                                .map(|error| format!("開けませんでした: {}", error));
+                        }
+                    }
+                    Message::ClosePreview => {
+                        state.preview = None;
                     }
                     _ => {}
                 }
 
-                if !saved {
-                    state.dirty = true;
+                if state
+                    .toast
+                    .as_ref()
+                    .is_some_and(|toast| toast.change != state.history.recorded())
+                {
+                    state.toast = None;
                 }
 
-                if state.dirty && !state.saving {
-                    state.dirty = false;
-                    state.saving = true;
+                if before.is_some_and(|before| before != state.to_saved()) {
+                    state.dirty = true;
+                }
 
-                    Command::perform(
-                        SavedState {
-                            input_value: state.input_value.clone(),
-                            filter: state.filter,
-                            tasks: state.tasks.clone(),
-                        }
-                        .save(),
-                        Message::Saved,
-                    )
+                // Later changes join the save already waiting or, when one
+                // is under way, get their own once it is done.
+                if state.dirty
+                    && !state.save_scheduled
+                    && !state.saving
+                    && !state.closing
+                    && state.can_save()
+                {
+                    state.save_scheduled = true;
+
+                    Command::batch(vec![
+                        command,
+                        Command::perform(async_std::task::sleep(SAVE_DELAY), |_| Message::SaveDue),
+                    ])
                 } else {
-                    Command::none()
+                    command
                 }
             }
         }
     }
 
-    fn view(&mut self) -> Element<Message> {
+    fn should_exit(&self) -> bool {
+        match self {
+            Todos::Loading(_) => false,
+            Todos::Loaded(state) => state.closing && !state.saving,
+        }
+    }
+
+    fn view(&mut self) -> Element<'_, Message> {
         match self {
-            Todos::Loading => loading_message(),
+            Todos::Loading(_) => loading_message(),
+            Todos::Loaded(State {
+                load_failure: Some(failure),
+                path,
+                recent,
+                file_menu,
+                retry_button,
+                start_fresh_button,
+                backup_panel,
+                ..
+            }) => Column::new()
+                .push(Container::new(file_menu.view(recent)).padding(10))
+                .push(load_failure_message(
+                    failure,
+                    path,
+                    retry_button,
+                    start_fresh_button,
+                    backup_panel,
+                ))
+                .into(),
+            Todos::Loaded(State {
+                preview: Some(preview),
+                ..
+            }) => preview.view(),
             Todos::Loaded(State {
                 scroll,
                 input,
                 input_value,
                 filter,
-                tasks,
+                sort,
+                importances,
+                auto_complete,
+                tags,
+                store,
+                current_list,
+                task_states,
+                drop_target,
+                files,
+                icons,
+                thumbnails,
+                texts,
                 controls,
+                tag_sidebar,
+                list_sidebar,
                 filter_input_value,
                 filter_input,
                 selected_importance,
+                due_input,
+                due_input_value,
+                due_unreadable,
+                backup_panel,
+                toast,
+                recent,
+                file_menu,
+                merge,
+                path,
+                access,
+                unlock_button,
+                error,
+                dismiss_error_button,
                 ..
             }) => {
-
                 let _title = Text::new("todos")
                     .width(Length::Fill)
                     .size(100)
                     .color([0.5, 0.5, 0.5])
                     .horizontal_alignment(iced::HorizontalAlignment::Center);
 
-                let filter_textbox =  TextInput::new(
+                let filter_textbox = TextInput::new(
                     filter_input,
-                           "",
-                           filter_input_value,
-                            Message::FilterTextChanged,
-                        );
-
-
-                let input = TextInput::new(
-                    input,
-                    "何をする？",
-                    input_value,
-                    Message::InputChanged,
+                    "",
+                    filter_input_value,
+                    Message::FilterTextChanged,
+                );
+
+                let input = TextInput::new(input, "何をする？", input_value, Message::InputChanged)
+                    .padding(15)
+                    .size(30)
+                    .on_submit(Message::CreateTask);
+
+                let due_input = TextInput::new(
+                    due_input,
+                    "期限 YYYY/MM/DD",
+                    due_input_value,
+                    Message::DueChanged,
                 )
-                .padding(15)
-                .size(30)
+                .width(Length::Units(200))
+                .padding(5)
                 .on_submit(Message::CreateTask);
 
-                let importance_selector = Column::new()
-                .push(Importance::all().iter().cloned().fold(
-                    Row::new().spacing(5),
-                    |choices, importance| {
-                        choices.push(Radio::new(
-                            importance,
-                            importance,
-                            *selected_importance,
-                            Message::ImportanceChanged,
-                        ).text_size(20).size(20).spacing(5))
+                let importance_selector = Row::new()
+                    .spacing(20)
+                    .align_items(Align::Center)
+                    .push(Importance::all().iter().cloned().fold(
+                        Row::new().spacing(5).width(Length::Fill),
+                        |choices, importance| {
+                            choices.push(
+                                Radio::new(
+                                    importance,
+                                    importance,
+                                    *selected_importance,
+                                    Message::ImportanceChanged,
+                                )
+                                .text_size(20)
+                                .size(20)
+                                .spacing(5),
+                            )
+                        },
+                    ))
+                    .push(
+                        Column::new().spacing(5).push(due_input).push(
+                            Text::new(if *due_unreadable {
+                                "期限は YYYY/MM/DD の形式で入力してください"
+                            } else {
+                                ""
+                            })
+                            .size(14)
+                            .color([0.8, 0.2, 0.2]),
+                        ),
+                    );
+
+                let controls = controls.view(
+                    Counts {
+                        left: store.tasks_left(
+                            Some(*current_list),
+                            filter_input_value,
+                            importances,
+                            tags,
+                        ),
+                        overdue: store.overdue(
+                            Some(*current_list),
+                            filter_input_value,
+                            importances,
+                            tags,
+                        ),
+                        left_in_all_lists: store.tasks_left(
+                            None,
+                            filter_input_value,
+                            importances,
+                            tags,
+                        ),
                     },
-                ));
-
-                let controls = controls.view(&tasks, *filter);
-                let filtered_tasks = tasks.iter().filter(|task| filter.matches(task) & filter.word_matches(task, filter_input_value));
-
-
-                let tasks: Element<_> = if filtered_tasks.count() > 0 {
-                    tasks
+                    *filter,
+                    *sort,
+                    importances,
+                    *auto_complete,
+                );
+
+                let tasks: Element<_> = if store
+                    .query(
+                        Some(*current_list),
+                        *filter,
+                        filter_input_value,
+                        importances,
+                        tags,
+                    )
+                    .count()
+                    > 0
+                {
+                    let mut task_states = task_states
                         .iter_mut()
-                        .enumerate()
-                        .filter(|(_, task)| filter.matches(task) & filter.word_matches(task, filter_input_value))
-                        .fold(Column::new().spacing(20), |column, (i, task)| {
+                        .map(|(id, task_state)| (*id, task_state))
+                        .collect::<HashMap<_, _>>();
+
+                    let movable = *sort == Sort::Manual;
+
+                    store
+                        .sorted(
+                            Some(*current_list),
+                            *filter,
+                            filter_input_value,
+                            importances,
+                            tags,
+                            *sort,
+                        )
+                        .into_iter()
+                        .filter_map(|task| Some((task, task_states.remove(&task.id)?)))
+                        .fold(Column::new().spacing(20), |column, (task, task_state)| {
+                            let id = task.id;
+
                             column.push(
-                                task.view()
-                                    .map(move |message| Message::TaskMessage(i, message)),
+                                task_state
+                                    .view(
+                                        task,
+                                        movable,
+                                        *drop_target == Some(id),
+                                        files,
+                                        icons,
+                                        thumbnails,
+                                        texts,
+                                    )
+                                    .map(move |message| Message::TaskMessage(id, message)),
                             )
                         })
                         .into()
@@ -293,246 +1666,132 @@ impl Application for Todos {
                         Filter::All => "まだ何のタスクもありません...",
                         Filter::Active => "タスクを全て完了しました :D",
                         Filter::Completed => "まだ何のタスクも完了していません...",
+                        Filter::Overdue => "期限切れのタスクはありません :D",
+                        Filter::Today => "今日が期限のタスクはありません...",
+                        Filter::ThisWeek => "今週が期限のタスクはありません...",
                     })
                 };
 
-                let content = Column::new()
+                let mut content = Column::new()
                     .max_width(800)
                     .spacing(20)
-                    .push(input)
-                    .push(importance_selector)
-                    .push(filter_textbox)
-                    .push(controls)
-                    .push(tasks);
-
-                Scrollable::new(scroll)
-                    .padding(40)
-                    .push(Container::new(content).width(Length::Fill).center_x())
-                    .into()
-            }
-        }
-    }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct Task {
-    description: String,
-    file_path: PathBuf,
-    completed: bool,
-    date: String,
-    importance: Importance,
-    #[serde(skip)]
-    state: TaskState,
-}
-
-#[derive(Debug, Clone)]
-pub enum TaskState {
-    Idle {
-        edit_button: button::State,
-        start_process_button: button::State,
-    },
-    Editing {
-        text_input: text_input::State,
-        delete_button: button::State,
-    },
-}
-
-impl Default for TaskState {
-    fn default() -> Self {
-        TaskState::Idle {
-            edit_button: button::State::new(),
-            start_process_button: button::State::new(),
-        }
-    }
-}
+                    .push(file_menu.view(recent));
 
-#[derive(Debug, Clone)]
-pub enum TaskMessage {
-    Completed(bool),
-    Edit,
-    DescriptionEdited(String),
-    FinishEdition,
-    Delete,
-    StartProcess(PathBuf),
-}
-
-impl Task {
-    fn new(description: String, file_path: PathBuf,date: String , importance: Importance) -> Self {
-        Task {
-            description,
-            completed: false,
-            file_path,
-            date,
-            importance,
-            state: TaskState::Idle {
-                edit_button: button::State::new(),
-                start_process_button: button::State::new(),
-            },
-        }
-    }
-
-    fn update(&mut self, message: TaskMessage) {
-        match message {
-            TaskMessage::Completed(completed) => {
-                self.completed = completed;
-            }
-            TaskMessage::Edit => {
-                let text_input = text_input::State::focused();
-                self.state = TaskState::Editing {
-                    text_input,
-                    delete_button: button::State::new(),
-                };
-            }
-            TaskMessage::DescriptionEdited(new_description) => {
-                self.description = new_description;
-            }
-            TaskMessage::FinishEdition => {
-                if !self.description.is_empty() {
-                    self.state = TaskState::Idle {
-                        edit_button: button::State::new(),
-                        start_process_button: button::State::new(),
-                    }
+                if let Some(error) = error {
+                    content = content.push(error_message(error, dismiss_error_button));
                 }
-            }
-
-            TaskMessage::StartProcess(process) => {
-                open::that(process).unwrap();
-            }
-            TaskMessage::Delete => {}
-        }
-    }
-
-    fn view(&mut self) -> Element<TaskMessage> {
-        match &mut self.state {
-            TaskState::Idle {
-                edit_button,
-                start_process_button,
-            } => {
-                let checkbox =
-                    Checkbox::new(self.completed, &self.description, TaskMessage::Completed)
-                        .width(Length::Fill);
-
-                let important = Text::new(self.importance).width(Length::Fill);
-
-                let filename = match self.file_path.file_name(){
-                    Some(result) => result.to_str().unwrap().to_string(),
-                    None => String::new(),
-                    };
-                
-                let file_extention = match self.file_path.extension() {
-                    Some(result) => result.to_str().unwrap().to_string(),
-                    None => String::new(),
-                    };
 
-                let image = match file_extention.as_str() {
-                    "txt" => Image::new("icons/icons8-txt-48.png")
-                    .width(Length::Units(30))
-                    .height(Length::Units(30)),
+                if let Access::ReadOnly(reason) = access {
+                    content = content.push(read_only_message(reason, path, unlock_button));
+                }
 
-                    "xlsx" => Image::new("icons/icons8-xls-48.png")
-                    .width(Length::Units(30))
-                    .height(Length::Units(30)),
+                if let Some(merge) = merge {
+                    content = content.push(merge.view(path));
+                }
 
-                    "jpg" => Image::new("icons/icons8-jpg-48.png")
-                    .width(Length::Units(30))
-                    .height(Length::Units(30)),
+                if let Some(Toast {
+                    message,
+                    undo_button,
+                    ..
+                }) = toast
+                {
+                    content = content.push(
+                        Container::new(
+                            Row::new()
+                                .spacing(20)
+                                .align_items(Align::Center)
+                                .push(Text::new(message.as_str()).size(16).width(Length::Fill))
+                                .push(
+                                    Button::new(undo_button, Text::new("Undo").size(16))
+                                        .on_press(Message::Undo)
+                                        .padding(8)
+                                        .style(style::Button::FilterSelected),
+                                ),
+                        )
+                        .width(Length::Fill)
+                        .padding(10)
+                        .style(style::Container::Toast),
+                    );
+                }
 
-                    "exe" => Image::new("icons/icons8-exe-48.png")
-                    .width(Length::Units(30))
-                    .height(Length::Units(30)),
+                let content = content
+                    .push(input)
+                    .push(importance_selector)
+                    .push(filter_textbox)
+                    .push(controls)
+                    .push(tasks)
+                    .push(backup_panel.view());
 
-                    "zip" => Image::new("icons/icons8-zip-48.png")
-                    .width(Length::Units(30))
-                    .height(Length::Units(30)),
+                let mut tag_counts = store.tag_counts(Some(*current_list));
 
-                    _ => Image::new("")
-                    .width(Length::Units(30))
-                    .height(Length::Units(30)),
-                };
+                // A selected tag stays listed after its last task is gone, so
+                // it can still be unselected.
+                for tag in &tags.tags {
+                    tag_counts.entry(tag).or_insert(0);
+                }
 
-                
-                let datetime_text = Text::new(&self.date);
+                let mut sidebar = Column::new()
+                    .spacing(30)
+                    .push(list_sidebar.view(store, *current_list));
 
+                if !tag_counts.is_empty() {
+                    sidebar = sidebar.push(tag_sidebar.view(&tag_counts, tags));
+                }
 
-                Column::new()
-                    .push(
-                        Row::new()
-                            .spacing(20)
-                            .align_items(Align::Center)
-                            .push(checkbox)
-                            .push(
-                                Button::new(edit_button, edit_icon())
-                                    .on_press(TaskMessage::Edit)
-                                    .padding(10)
-                                    .style(style::Button::Icon),
-                            ),
-                    )
-                    .push(
-                        Row::new()
-                        .push(Button::new(start_process_button, image).on_press(
-                                TaskMessage::StartProcess(PathBuf::from(&self.file_path))))
-                        .push(Space::new(Length::Units(5),Length::Units(5)))
-                        .push(Text::new(filename)).align_items(Align::End)
-                        .push(Space::new(Length::Fill,Length::Units(5)))
-                    )
-                    .push(important)
-                    .push(Space::new(Length::Fill,Length::Units(5)))
-                    .push(datetime_text).align_items(Align::End)
-                    .into()
-            }
-            TaskState::Editing {
-                text_input,
-                delete_button,
-            } => {
-                let text_input = TextInput::new(
-                    text_input,
-                    "Describe your task...",
-                    &self.description,
-                    TaskMessage::DescriptionEdited,
-                )
-                .on_submit(TaskMessage::FinishEdition)
-                .padding(10);
+                let page = Row::new().spacing(40).push(sidebar).push(content);
 
- 
-                Row::new()
-                    .spacing(20)
-                    .align_items(Align::Center)
-                    .push(text_input)
-                    .push(
-                        Button::new(
-                            delete_button,
-                            Row::new()
-                                .spacing(10)
-                                .push(delete_icon())
-                                .push(Text::new("Delete")),
-                        )
-                        .on_press(TaskMessage::Delete)
-                        .padding(10)
-                        .style(style::Button::Destructive),
-                    )
+                Scrollable::new(scroll)
+                    .padding(40)
+                    .push(Container::new(page).width(Length::Fill).center_x())
                     .into()
             }
         }
     }
 }
 
+/// Task counts shown above the filters.
+#[derive(Debug, Clone, Copy)]
+struct Counts {
+    /// Uncompleted tasks of the current list.
+    left: usize,
+    overdue: usize,
+    left_in_all_lists: usize,
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct Controls {
     all_button: button::State,
     active_button: button::State,
     completed_button: button::State,
+    overdue_button: button::State,
+    today_button: button::State,
+    this_week_button: button::State,
+    importance_buttons: [button::State; 3],
+    sort_list: pick_list::State<Sort>,
 }
 
 impl Controls {
-    fn view(&mut self, tasks: &[Task], current_filter: Filter) -> Row<Message> {
+    /// Renders the filters, above the `counts` of the tasks the other
+    /// filters let through.
+    fn view(
+        &mut self,
+        counts: Counts,
+        current_filter: Filter,
+        current_sort: Sort,
+        importances: &[Importance],
+        auto_complete: bool,
+    ) -> Column<'_, Message> {
         let Controls {
             all_button,
             active_button,
             completed_button,
+            overdue_button,
+            today_button,
+            this_week_button,
+            importance_buttons,
+            sort_list,
         } = self;
 
-        let tasks_left = tasks.iter().filter(|task| !task.completed).count();
-
         let filter_button = |state, label, filter, current_filter| {
             let label = Text::new(label).size(16);
             let button = Button::new(state, label).style(if filter == current_filter {
@@ -544,22 +1803,56 @@ impl Controls {
             button.on_press(Message::FilterChanged(filter)).padding(8)
         };
 
+        let sort_selector = PickList::new(
+            sort_list,
+            &Sort::ALL[..],
+            Some(current_sort),
+            Message::SortChanged,
+        )
+        .text_size(16)
+        .padding(8);
 
-        Row::new()
-            .spacing(20)
-            .align_items(Align::Center)
+        Column::new()
+            .spacing(10)
             .push(
-                Text::new(format!(
-                    "{} {} left",
-                    tasks_left,
-                    if tasks_left == 1 { "task" } else { "tasks" }
-                ))
-                .width(Length::Fill)
-                .size(16),
+                Row::new()
+                    .spacing(10)
+                    .align_items(Align::Center)
+                    .push(
+                        Text::new(format!(
+                            "{} {} left{} ({} in all lists)",
+                            counts.left,
+                            if counts.left == 1 { "task" } else { "tasks" },
+                            if counts.overdue > 0 {
+                                format!(", {} overdue", counts.overdue)
+                            } else {
+                                String::new()
+                            },
+                            counts.left_in_all_lists,
+                        ))
+                        .width(Length::Fill)
+                        .size(16),
+                    )
+                    .push(Importance::all().iter().zip(importance_buttons).fold(
+                        Row::new().spacing(5),
+                        |row, (&importance, state)| {
+                            row.push(
+                                Button::new(state, Text::new(importance).size(16))
+                                    .style(if importances.contains(&importance) {
+                                        style::Button::FilterSelected
+                                    } else {
+                                        style::Button::FilterActive
+                                    })
+                                    .on_press(Message::ImportanceFilterToggled(importance))
+                                    .padding(8),
+                            )
+                        },
+                    ))
+                    .push(Text::new("Sort by").size(16))
+                    .push(sort_selector),
             )
             .push(
                 Row::new()
-                    .width(Length::Shrink)
                     .spacing(10)
                     .push(filter_button(
                         all_button,
@@ -578,40 +1871,741 @@ impl Controls {
                         "Completed",
                         Filter::Completed,
                         current_filter,
-                    )),
+                    ))
+                    .push(filter_button(
+                        overdue_button,
+                        "Overdue",
+                        Filter::Overdue,
+                        current_filter,
+                    ))
+                    .push(filter_button(
+                        today_button,
+                        "Today",
+                        Filter::Today,
+                        current_filter,
+                    ))
+                    .push(filter_button(
+                        this_week_button,
+                        "This Week",
+                        Filter::ThisWeek,
+                        current_filter,
+                    ))
+                    .push(Space::with_width(Length::Fill))
+                    .push(
+                        Checkbox::new(
+                            auto_complete,
+                            "Complete tasks with their subtasks",
+                            Message::AutoCompleteToggled,
+                        )
+                        .text_size(16)
+                        .size(16),
+                    ),
             )
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-pub enum Filter {
-    All,
-    Active,
-    Completed,
+/// Asks what to do when the file changed outside the app while the app had
+/// unsaved changes, one choice per task changed on both sides.
+#[derive(Debug)]
+struct MergePrompt {
+    merge: Merge,
+    /// The file as just read; it becomes the base of the next save.
+    remote: OnDisk,
+    /// Whether the file's version wins, per conflict.
+    take_remote: Vec<bool>,
+    choice_buttons: Vec<(button::State, button::State)>,
+    merge_button: button::State,
+    keep_mine_button: button::State,
+    take_theirs_button: button::State,
 }
 
-impl Default for Filter {
-    fn default() -> Self {
-        Filter::All
+impl MergePrompt {
+    fn new(base: &OnDisk, local: SavedState, remote: TaskStore, stamp: Option<Stamp>) -> Self {
+        let merge = Merge::new(
+            (&base.lists, &base.tasks),
+            (&local.lists, &local.tasks),
+            (remote.lists(), remote.tasks()),
+        );
+        let conflicts = merge.conflicts.len();
+
+        MergePrompt {
+            merge,
+            remote: OnDisk {
+                stamp,
+                lists: remote.lists().to_vec(),
+                tasks: remote.tasks().to_vec(),
+            },
+            take_remote: vec![false; conflicts],
+            choice_buttons: (0..conflicts).map(|_| Default::default()).collect(),
+            merge_button: button::State::new(),
+            keep_mine_button: button::State::new(),
+            take_theirs_button: button::State::new(),
+        }
+    }
+
+    fn view(&mut self, path: &Path) -> Element<'_, Message> {
+        let MergePrompt {
+            merge,
+            take_remote,
+            choice_buttons,
+            merge_button,
+            keep_mine_button,
+            take_theirs_button,
+            ..
+        } = self;
+
+        let describe = |task: &Option<Task>| match task {
+            Some(task) => task.description.clone(),
+            None => String::from("(deleted)"),
+        };
+
+        let choice = |state, label, selected, message| {
+            Button::new(state, Text::new(label).size(14))
+                .on_press(message)
+                .padding(6)
+                .style(if selected {
+                    style::Button::FilterSelected
+                } else {
+                    style::Button::FilterActive
+                })
+        };
+
+        let conflicts = merge
+            .conflicts
+            .iter()
+            .zip(choice_buttons)
+            .zip(take_remote.iter())
+            .enumerate()
+            .fold(
+                Column::new().spacing(5),
+                |column, (index, ((conflict, (mine, theirs)), &take_remote))| {
+                    column.push(
+                        Row::new()
+                            .spacing(10)
+                            .align_items(Align::Center)
+                            .push(
+                                choice(
+                                    mine,
+                                    format!("Mine: {}", describe(&conflict.local)),
+                                    !take_remote,
+                                    Message::ConflictChoice(index, false),
+                                )
+                                .width(Length::Fill),
+                            )
+                            .push(
+                                choice(
+                                    theirs,
+                                    format!("File: {}", describe(&conflict.remote)),
+                                    take_remote,
+                                    Message::ConflictChoice(index, true),
+                                )
+                                .width(Length::Fill),
+                            ),
+                    )
+                },
+            );
+
+        let summary = match merge.conflicts.len() {
+            0 => String::from("The changes do not overlap and can be merged."),
+            1 => String::from("One task was changed on both sides; pick the version to keep."),
+            conflicts => format!(
+                "{} tasks were changed on both sides; pick the versions to keep.",
+                conflicts
+            ),
+        };
+
+        let actions = Row::new()
+            .spacing(10)
+            .push(
+                Button::new(merge_button, Text::new("Merge").size(16))
+                    .on_press(Message::ApplyMerge)
+                    .padding(8)
+                    .style(style::Button::FilterSelected),
+            )
+            .push(
+                Button::new(keep_mine_button, Text::new("Keep only mine").size(16))
+                    .on_press(Message::KeepMine)
+                    .padding(8)
+                    .style(style::Button::FilterActive),
+            )
+            .push(
+                Button::new(take_theirs_button, Text::new("Discard mine").size(16))
+                    .on_press(Message::TakeTheirs)
+                    .padding(8)
+                    .style(style::Button::Destructive),
+            );
+
+        Container::new(
+            Column::new()
+                .spacing(10)
+                .push(
+                    Text::new(format!(
+                        "{} was changed outside the app while you had unsaved changes.",
+                        path.display()
+                    ))
+                    .size(16),
+                )
+                .push(Text::new(summary).size(16))
+                .push(conflicts)
+                .push(actions),
+        )
+        .width(Length::Fill)
+        .padding(10)
+        .style(style::Container::Error)
+        .into()
     }
 }
 
-impl Filter {
-    fn matches(&self, task: &Task) -> bool {
-        match self {
-            Filter::All => true,
-            Filter::Active => !task.completed,
-            Filter::Completed => task.completed,
+/// New, Open and Save As, and the files opened last; iced has no menu bar,
+/// so this is a row of buttons that folds out.
+#[derive(Debug, Default, Clone)]
+struct FileMenu {
+    open: bool,
+    toggle_button: button::State,
+    new_button: button::State,
+    open_button: button::State,
+    save_as_button: button::State,
+    recent_buttons: Vec<button::State>,
+}
+
+impl FileMenu {
+    fn view(&mut self, recent: &RecentFiles) -> Element<'_, Message> {
+        let FileMenu {
+            open,
+            toggle_button,
+            new_button,
+            open_button,
+            save_as_button,
+            recent_buttons,
+        } = self;
+
+        let item = |state, label, message| {
+            Button::new(state, Text::new(label).size(16))
+                .on_press(message)
+                .padding(8)
+                .style(style::Button::FilterActive)
+        };
+
+        let toggle = Button::new(
+            toggle_button,
+            Text::new(if *open { "File ▾" } else { "File ▸" }).size(16),
+        )
+        .on_press(Message::ToggleFileMenu)
+        .padding(8)
+        .style(if *open {
+            style::Button::FilterSelected
+        } else {
+            style::Button::FilterActive
+        });
+
+        let menu = Row::new().spacing(10).push(toggle);
+
+        if !*open {
+            return menu.into();
+        }
+
+        let menu = menu
+            .push(item(new_button, "New…", Message::NewFile))
+            .push(item(open_button, "Open…", Message::OpenFile))
+            .push(item(save_as_button, "Save As…", Message::SaveAs));
+
+        recent_buttons.resize_with(recent.paths().len(), Default::default);
+
+        let recent = recent.paths().iter().zip(recent_buttons).fold(
+            Column::new().spacing(2),
+            |column, (path, state)| {
+                column.push(
+                    Button::new(state, Text::new(path.to_string_lossy()).size(14))
+                        .on_press(Message::OpenRecent(path.clone()))
+                        .padding(4)
+                        .style(style::Button::Icon),
+                )
+            },
+        );
+
+        Column::new()
+            .spacing(10)
+            .push(menu)
+            .push(Text::new("Recent files").size(14).color([0.5, 0.5, 0.5]))
+            .push(recent)
+            .into()
+    }
+}
+
+/// Switches between the task lists and manages them.
+#[derive(Debug, Default, Clone)]
+struct ListSidebar {
+    list_buttons: Vec<button::State>,
+    new_list_input: text_input::State,
+    new_list_value: String,
+    /// The name field while the current list is being renamed.
+    renaming: Option<(text_input::State, String)>,
+    rename_button: button::State,
+    archive_button: button::State,
+    delete_button: button::State,
+    show_archived: bool,
+}
+
+impl ListSidebar {
+    fn view(&mut self, store: &TaskStore, current_list: ListId) -> Element<'_, Message> {
+        let ListSidebar {
+            list_buttons,
+            new_list_input,
+            new_list_value,
+            renaming,
+            rename_button,
+            archive_button,
+            delete_button,
+            show_archived,
+        } = self;
+
+        let no_tags = TagFilter::default();
+        let lists = store
+            .lists()
+            .iter()
+            .filter(|list| *show_archived || !list.archived || list.id == current_list)
+            .collect::<Vec<_>>();
+
+        list_buttons.resize_with(lists.len(), Default::default);
+
+        let sidebar = lists.iter().zip(list_buttons).fold(
+            Column::new()
+                .spacing(5)
+                .width(Length::Units(180))
+                .push(Text::new("Lists").size(20)),
+            |sidebar, (list, state)| {
+                let left = store.tasks_left(Some(list.id), "", &[], &no_tags);
+                let mut label = format!("{} ({})", list.name, left);
+
+                if list.archived {
+                    label += " · archived";
+                }
+
+                sidebar.push(
+                    Button::new(state, Text::new(label).size(16))
+                        .width(Length::Fill)
+                        .style(if list.id == current_list {
+                            style::Button::FilterSelected
+                        } else {
+                            style::Button::FilterActive
+                        })
+                        .on_press(Message::ListSelected(list.id))
+                        .padding(6),
+                )
+            },
+        );
+
+        let sidebar = sidebar.push(
+            TextInput::new(
+                new_list_input,
+                "New list",
+                new_list_value,
+                Message::NewListChanged,
+            )
+            .on_submit(Message::CreateList)
+            .padding(6)
+            .size(16),
+        );
+
+        let sidebar = match renaming {
+            Some((input, name)) => sidebar.push(
+                TextInput::new(input, "List name", name, Message::ListNameChanged)
+                    .on_submit(Message::FinishRenaming)
+                    .padding(6)
+                    .size(16),
+            ),
+            None => sidebar.push(
+                Button::new(rename_button, Text::new("Rename").size(14))
+                    .on_press(Message::RenameList)
+                    .padding(6)
+                    .style(style::Button::FilterActive),
+            ),
+        };
+
+        let archived = store.list(current_list).is_some_and(|list| list.archived);
+
+        // The inbox is where tasks go by default, so it always stays.
+        let sidebar = if current_list == ListId::INBOX {
+            sidebar
+        } else {
+            sidebar.push(
+                Row::new()
+                    .spacing(5)
+                    .push(
+                        Button::new(
+                            archive_button,
+                            Text::new(if archived { "Restore" } else { "Archive" }).size(14),
+                        )
+                        .on_press(Message::ArchiveList(current_list, !archived))
+                        .padding(6)
+                        .style(style::Button::FilterActive),
+                    )
+                    .push(
+                        Button::new(delete_button, Text::new("Delete").size(14))
+                            .on_press(Message::DeleteList(current_list))
+                            .padding(6)
+                            .style(style::Button::Destructive),
+                    ),
+            )
+        };
+
+        sidebar
+            .push(
+                Checkbox::new(
+                    *show_archived,
+                    "Show archived",
+                    Message::ShowArchivedToggled,
+                )
+                .text_size(14)
+                .size(14),
+            )
+            .into()
+    }
+}
+
+/// Every tag in use with its number of tasks; clicking one filters the list
+/// by it.
+#[derive(Debug, Default, Clone)]
+struct TagSidebar {
+    tag_buttons: Vec<button::State>,
+    any_button: button::State,
+    all_button: button::State,
+    clear_button: button::State,
+}
+
+impl TagSidebar {
+    fn view(
+        &mut self,
+        tag_counts: &BTreeMap<&str, usize>,
+        selected: &TagFilter,
+    ) -> Element<'_, Message> {
+        let TagSidebar {
+            tag_buttons,
+            any_button,
+            all_button,
+            clear_button,
+        } = self;
+
+        let mode_button = |state, label, mode| {
+            Button::new(state, Text::new(label).size(14))
+                .style(if selected.mode == mode {
+                    style::Button::FilterSelected
+                } else {
+                    style::Button::FilterActive
+                })
+                .on_press(Message::TagModeChanged(mode))
+                .padding(6)
+        };
+
+        let mut sidebar = Column::new()
+            .spacing(10)
+            .width(Length::Units(180))
+            .push(Text::new("Tags").size(20))
+            .push(
+                Row::new()
+                    .spacing(5)
+                    .push(mode_button(any_button, "Any", TagMode::Any))
+                    .push(mode_button(all_button, "All", TagMode::All)),
+            );
+
+        tag_buttons.resize_with(tag_counts.len(), Default::default);
+
+        sidebar =
+            tag_counts
+                .iter()
+                .zip(tag_buttons)
+                .fold(sidebar, |sidebar, ((&tag, &count), state)| {
+                    sidebar.push(
+                        task::tag_chip(
+                            state,
+                            tag,
+                            format!("#{} ({})", tag, count),
+                            selected.tags.contains(tag),
+                        )
+                        .on_press(Message::TagToggled(tag.to_string())),
+                    )
+                });
+
+        if !selected.tags.is_empty() {
+            sidebar = sidebar.push(
+                Button::new(clear_button, Text::new("Clear").size(14))
+                    .on_press(Message::ClearTags)
+                    .padding(6)
+                    .style(style::Button::FilterActive),
+            );
+        }
+
+        sidebar.into()
+    }
+}
+
+/// An image attachment shown at the size of the window.
+#[derive(Debug)]
+struct Preview {
+    path: PathBuf,
+    open_button: button::State,
+    close_button: button::State,
+    error: Option<String>,
+}
+
+impl Preview {
+    fn new(path: PathBuf) -> Self {
+        Preview {
+            path,
+            open_button: button::State::new(),
+            close_button: button::State::new(),
+            error: None,
+        }
+    }
+
+    fn view(&mut self) -> Element<'_, Message> {
+        let header = Row::new()
+            .spacing(10)
+            .align_items(Align::Center)
+            .push(
+                Text::new(self.path.to_string_lossy())
+                    .size(16)
+                    .width(Length::Fill),
+            )
+            .push(
+                Button::new(&mut self.open_button, Text::new("Open").size(16))
+                    .on_press(Message::OpenPreviewed)
+                    .padding(8)
+                    .style(style::Button::FilterActive),
+            )
+            .push(
+                Button::new(&mut self.close_button, Text::new("Close").size(16))
+                    .on_press(Message::ClosePreview)
+                    .padding(8)
+                    .style(style::Button::FilterSelected),
+            );
+
+        let mut content = Column::new().spacing(20).push(header);
+
+        if let Some(error) = &self.error {
+            content = content.push(Text::new(error.as_str()).size(16).color([0.8, 0.2, 0.2]));
         }
+
+        let content = content.push(
+            Image::new(image::Handle::from_path(&self.path))
+                .width(Length::Fill)
+                .height(Length::Fill),
+        );
+
+        Container::new(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .padding(40)
+            .into()
+    }
+}
+
+/// Lists the rolling backups of the todo file and restores one on request.
+#[derive(Debug, Default)]
+struct BackupPanel {
+    toggle_button: button::State,
+    /// `Some` while the panel is open.
+    entries: Option<Vec<(Backup, button::State)>>,
+    error: Option<LoadError>,
+}
+
+impl BackupPanel {
+    fn show(&mut self, backups: Vec<Backup>) {
+        self.entries = Some(
+            backups
+                .into_iter()
+                .map(|backup| (backup, button::State::new()))
+                .collect(),
+        );
+        self.error = None;
     }
 
-    fn word_matches(&self, task: &Task ,filter_input_value: &String) -> bool {
-        task.description.contains(filter_input_value)
+    fn view(&mut self) -> Element<'_, Message> {
+        let BackupPanel {
+            toggle_button,
+            entries,
+            error,
+        } = self;
+
+        let toggle = Button::new(
+            toggle_button,
+            Text::new(if entries.is_some() {
+                "Hide backups"
+            } else {
+                "Restore from backup..."
+            })
+            .size(16),
+        )
+        .on_press(Message::ToggleBackups)
+        .padding(8)
+        .style(style::Button::FilterActive);
+
+        let mut panel = Column::new().spacing(10).push(toggle);
+
+        if let Some(error) = error {
+            panel = panel.push(
+                Text::new(format!("The backup could not be restored: {}.", error))
+                    .size(16)
+                    .color([0.8, 0.2, 0.2]),
+            );
+        }
+
+        match entries {
+            Some(entries) if entries.is_empty() => {
+                panel.push(Text::new("There are no backups yet.").size(16))
+            }
+            Some(entries) => entries
+                .iter_mut()
+                .fold(panel, |panel, (backup, restore_button)| {
+                    let tasks = match backup.tasks {
+                        Some(1) => String::from("1 task"),
+                        Some(tasks) => format!("{} tasks", tasks),
+                        None => String::from("unreadable"),
+                    };
+
+                    let kind = match backup.kind {
+                        BackupKind::Rolling(_) => "Backup",
+                        BackupKind::Unsaved => "Unsaved changes",
+                        BackupKind::Unreadable => "Unreadable file",
+                    };
+
+                    let mut restore = Button::new(restore_button, Text::new("Restore").size(16))
+                        .padding(8)
+                        .style(style::Button::FilterActive);
+
+                    if backup.tasks.is_some() {
+                        restore = restore.on_press(Message::RestoreBackup(backup.path.clone()));
+                    }
+
+                    panel.push(
+                        Row::new()
+                            .spacing(20)
+                            .align_items(Align::Center)
+                            .push(
+                                Text::new(backup.modified.format("%Y/%m/%d %H:%M:%S").to_string())
+                                    .size(16),
+                            )
+                            .push(Text::new(kind).size(16))
+                            .push(Text::new(tasks).size(16).width(Length::Fill))
+                            .push(restore),
+                    )
+                }),
+            None => panel,
+        }
+        .into()
     }
+}
+
+fn load_failure_message<'a>(
+    failure: &LoadFailure,
+    path: &Path,
+    retry_button: &'a mut button::State,
+    start_fresh_button: &'a mut button::State,
+    backup_panel: &'a mut BackupPanel,
+) -> Element<'a, Message> {
+    let backup = match &failure.backup {
+        Some(backup) => format!("A copy was saved as {}.", backup.display()),
+        None => String::from("A backup copy could not be made."),
+    };
+
+    let banner = Column::new()
+        .spacing(15)
+        .push(
+            Text::new(format!(
+                "{} could not be loaded: {}.",
+                path.display(),
+                failure.error
+            ))
+            .size(20),
+        )
+        .push(Text::new(backup).size(16))
+        .push(
+            Text::new("Nothing will be saved until the file is fixed or you start over.").size(16),
+        )
+        .push(
+            Row::new()
+                .spacing(10)
+                .push(
+                    Button::new(retry_button, Text::new("Retry").size(16))
+                        .on_press(Message::RetryLoad)
+                        .padding(8)
+                        .style(style::Button::FilterActive),
+                )
+                .push(
+                    Button::new(
+                        start_fresh_button,
+                        Text::new("Start with an empty list").size(16),
+                    )
+                    .on_press(Message::StartFresh)
+                    .padding(8)
+                    .style(style::Button::Destructive),
+                ),
+        )
+        .push(backup_panel.view());
+
+    Container::new(
+        Container::new(banner)
+            .max_width(800)
+            .padding(20)
+            .style(style::Container::Error),
+    )
+    .width(Length::Fill)
+    .padding(40)
+    .center_x()
+    .into()
+}
+
+fn error_message<'a>(error: &str, dismiss_button: &'a mut button::State) -> Element<'a, Message> {
+    Container::new(
+        Row::new()
+            .spacing(20)
+            .align_items(Align::Center)
+            .push(Text::new(error).size(16).width(Length::Fill))
+            .push(
+                Button::new(dismiss_button, Text::new("Dismiss").size(16))
+                    .on_press(Message::DismissError)
+                    .padding(8)
+                    .style(style::Button::FilterActive),
+            ),
+    )
+    .width(Length::Fill)
+    .padding(10)
+    .style(style::Container::Error)
+    .into()
+}
 
-//    fn importance_matches(&self, task: &Task ,importance: &i8) -> bool {
-//
-//    }
+fn read_only_message<'a>(
+    reason: &Option<String>,
+    path: &Path,
+    unlock_button: &'a mut button::State,
+) -> Element<'a, Message> {
+    let message = match reason {
+        Some(reason) => format!("{} is read-only because {}.", path.display(), reason),
+        None => format!("{} is read-only.", path.display()),
+    };
+
+    Container::new(
+        Row::new()
+            .spacing(20)
+            .align_items(Align::Center)
+            .push(
+                Column::new()
+                    .spacing(5)
+                    .width(Length::Fill)
+                    .push(Text::new(message).size(16))
+                    .push(Text::new("Changes made here will not be saved.").size(16)),
+            )
+            .push(
+                Button::new(unlock_button, Text::new("Open for editing").size(16))
+                    .on_press(Message::Unlock)
+                    .padding(8)
+                    .style(style::Button::FilterActive),
+            ),
+    )
+    .width(Length::Fill)
+    .padding(10)
+    .style(style::Container::Error)
+    .into()
 }
 
 fn loading_message<'a>() -> Element<'a, Message> {
@@ -662,169 +2656,150 @@ fn delete_icon() -> Text {
     icon('\u{F1F8}')
 }
 
-// Persistence
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct SavedState {
-    input_value: String,
-    filter: Filter,
-    tasks: Vec<Task>,
+/// Asks for a file to attach, `None` if the dialog is dismissed.
+async fn browse_file() -> Option<PathBuf> {
+    tinyfiledialogs::open_file_dialog("Attach a file", "", None).map(PathBuf::from)
 }
 
-#[derive(Debug, Clone)]
-enum LoadError {
-    FileError,
-    FormatError,
-}
+/// Asks where the missing file at `path` is now, `None` if the dialog is
+/// dismissed.
+async fn locate_file(path: PathBuf) -> (PathBuf, Option<PathBuf>) {
+    let title = format!("Locate {}", path.display());
+    let new_path = tinyfiledialogs::open_file_dialog(&title, "", None).map(PathBuf::from);
 
-#[derive(Debug, Clone)]
-enum SaveError {
-    FileError,
-    WriteError,
-    FormatError,
+    (path, new_path)
 }
 
-#[cfg(not(target_arch = "wasm32"))]
-impl SavedState {
-    fn path() -> std::path::PathBuf {
-        let mut path = if let Some(project_dirs) =
-            directories_next::ProjectDirs::from("rs", "Iced", "Todos")
-        {
-            project_dirs.data_dir().into()
-        } else {
-            std::env::current_dir().unwrap_or(std::path::PathBuf::new())
-        };
+/// Asks for a todo file to open, `None` if the dialog is dismissed.
+async fn choose_file_to_open(current: PathBuf) -> Option<PathBuf> {
+    tinyfiledialogs::open_file_dialog(
+        "Open a todo file",
+        &current.to_string_lossy(),
+        Some((&["*.json"], "Todo files")),
+    )
+    .map(PathBuf::from)
+}
 
-        path.push("todos.json");
+/// Asks where to write a todo file, `None` if the dialog is dismissed.
+async fn choose_file_to_save(title: &'static str, current: PathBuf) -> Option<PathBuf> {
+    tinyfiledialogs::save_file_dialog_with_filter(
+        title,
+        &current.to_string_lossy(),
+        &["*.json"],
+        "Todo files",
+    )
+    .map(PathBuf::from)
+}
 
-        path
+// Persistence
+/// Reads the todo file at `path`; a file opened read-only is not written
+/// back after being migrated.
+async fn load(path: PathBuf, read_only: bool) -> Result<SavedState, LoadFailure> {
+    let loaded = if read_only {
+        SavedState::load_from(&path)
+    } else {
+        SavedState::load(&path)
+    };
+
+    match loaded {
+        Ok(state) => Ok(state),
+        // Nothing has been saved yet
+        Err(LoadError::FileError) if !path.exists() => Ok(SavedState::default()),
+        Err(error) => Err(LoadFailure {
+            error,
+            backup: SavedState::backup_unreadable(&path).ok(),
+        }),
     }
+}
 
-    async fn load() -> Result<SavedState, LoadError> {
-        use async_std::prelude::*;
+/// Writes the tasks of the file being left, then reads the one at `path`.
+async fn switch(
+    leaving: Option<Leaving>,
+    left: Access,
+    path: PathBuf,
+    read_only: bool,
+) -> Result<SavedState, LoadFailure> {
+    flush(leaving, left);
 
-        let mut contents = String::new();
+    load(path, read_only).await
+}
 
-        let mut file = async_std::fs::File::open(Self::path())
-            .await
-            .map_err(|_| LoadError::FileError)?;
+/// Writes the tasks of the file being left, then starts an empty file at
+/// `path`.
+async fn create(
+    leaving: Option<Leaving>,
+    left: Access,
+    path: PathBuf,
+) -> Result<SavedState, LoadFailure> {
+    flush(leaving, left);
 
-        file.read_to_string(&mut contents)
-            .await
-            .map_err(|_| LoadError::FileError)?;
+    let state = SavedState::default();
 
-        serde_json::from_str(&contents).map_err(|_| LoadError::FormatError)
+    if let Err(error) = state.save(&path) {
+        eprintln!("Failed to create {}: {}", path.display(), error);
     }
 
-    async fn save(self) -> Result<(), SaveError> {
-        use async_std::prelude::*;
-
-        let json = serde_json::to_string_pretty(&self).map_err(|_| SaveError::FormatError)?;
-
-        let path = Self::path();
-
-        if let Some(dir) = path.parent() {
-            async_std::fs::create_dir_all(dir)
-                .await
-                .map_err(|_| SaveError::FileError)?;
-        }
-
-        {
-            let mut file = async_std::fs::File::create(path)
-                .await
-                .map_err(|_| SaveError::FileError)?;
-
-            file.write_all(json.as_bytes())
-                .await
-                .map_err(|_| SaveError::WriteError)?;
-        }
-
-        // This is a simple way to save at most once every couple seconds
-        async_std::task::sleep(std::time::Duration::from_secs(2)).await;
-
-        Ok(())
-    }
+    Ok(state)
 }
 
-#[cfg(target_arch = "wasm32")]
-impl SavedState {
-    fn storage() -> Option<web_sys::Storage> {
-        let window = web_sys::window()?;
-
-        window.local_storage().ok()?
+/// Writes the file being left, and only then gives up its lock.
+fn flush(leaving: Option<Leaving>, left: Access) {
+    if let Some(leaving) = leaving {
+        put_away(leaving);
     }
 
-    async fn load() -> Result<SavedState, LoadError> {
-        let storage = Self::storage().ok_or(LoadError::FileError)?;
-
-        let contents = storage
-            .get_item("state")
-            .map_err(|_| LoadError::FileError)?
-            .ok_or(LoadError::FileError)?;
+    drop(left);
+}
 
-        serde_json::from_str(&contents).map_err(|_| LoadError::FormatError)
+/// Writes the tasks of a file being left. Those that cannot go into it,
+/// because it was changed outside the app or cannot be written, are put
+/// aside next to it. Whether they went into the file.
+fn put_away(leaving: Leaving) -> bool {
+    if !leaving.merging {
+        match write(&leaving.state, &leaving.path, leaving.expected) {
+            Ok(Some(_)) => return true,
+            Ok(None) => {}
+            Err(error) => eprintln!("Failed to save {}: {}", leaving.path.display(), error),
+        }
     }
 
-    async fn save(self) -> Result<(), SaveError> {
-        let storage = Self::storage().ok_or(SaveError::FileError)?;
-
-        let json = serde_json::to_string_pretty(&self).map_err(|_| SaveError::FormatError)?;
+    match leaving.state.save_aside(&leaving.path) {
+        Ok(aside) => eprintln!("The unsaved changes were written to {}", aside.display()),
+        Err(error) => eprintln!("Failed to put the unsaved changes aside: {}", error),
+    }
 
-        storage
-            .set_item("state", &json)
-            .map_err(|_| SaveError::WriteError)?;
+    false
+}
 
-        let _ = wasm_timer::Delay::new(std::time::Duration::from_secs(2)).await;
+async fn list_backups(path: PathBuf) -> Vec<Backup> {
+    SavedState::backups(&path)
+}
 
-        Ok(())
-    }
+async fn restore(path: PathBuf) -> Result<SavedState, LoadError> {
+    SavedState::load_from(&path)
 }
 
-mod style {
-    use iced::{button, Background, Color, Vector};
+/// [`write`] off the UI thread.
+async fn save(
+    state: SavedState,
+    path: PathBuf,
+    expected: Option<Stamp>,
+) -> Result<Option<OnDisk>, SaveError> {
+    write(&state, &path, expected)
+}
 
-    pub enum Button {
-        FilterActive,
-        FilterSelected,
-        Icon,
-        Destructive,
+/// Writes the tasks unless the file is no longer the version `expected`,
+/// i.e. someone else wrote it since it was last loaded or saved.
+fn write(
+    state: &SavedState,
+    path: &Path,
+    expected: Option<Stamp>,
+) -> Result<Option<OnDisk>, SaveError> {
+    if Stamp::of(path) != expected {
+        return Ok(None);
     }
 
-    impl button::StyleSheet for Button {
-        fn active(&self) -> button::Style {
-            match self {
-                Button::FilterActive => button::Style::default(),
-                Button::FilterSelected => button::Style {
-                    background: Some(Background::Color(Color::from_rgb(0.2, 0.2, 0.7))),
-                    border_radius: 10.0,
-                    text_color: Color::WHITE,
-                    ..button::Style::default()
-                },
-                Button::Icon => button::Style {
-                    text_color: Color::from_rgb(0.5, 0.5, 0.5),
-                    ..button::Style::default()
-                },
-                Button::Destructive => button::Style {
-                    background: Some(Background::Color(Color::from_rgb(0.8, 0.2, 0.2))),
-                    border_radius: 5.0,
-                    text_color: Color::WHITE,
-                    shadow_offset: Vector::new(1.0, 1.0),
-                    ..button::Style::default()
-                },
-            }
-        }
-
-        fn hovered(&self) -> button::Style {
-            let active = self.active();
+    state.save(path)?;
 
-            button::Style {
-                text_color: match self {
-                    Button::Icon => Color::from_rgb(0.2, 0.2, 0.7),
-                    Button::FilterActive => Color::from_rgb(0.2, 0.2, 0.7),
-                    _ => active.text_color,
-                },
-                shadow_offset: active.shadow_offset + Vector::new(0.0, 1.0),
-                ..active
-            }
-        }
-    }
+    Ok(Some(OnDisk::new(Stamp::of(path), state)))
 }
@@ -0,0 +1,54 @@
+//! The todo files opened last, offered in the File menu. They are kept in
+//! `recent.json` in the config directory, most recent first.
+
+use std::path::{Path, PathBuf};
+
+/// How many files the menu lists.
+const MAX_RECENT: usize = 8;
+
+#[derive(Debug, Clone, Default)]
+pub struct RecentFiles {
+    paths: Vec<PathBuf>,
+}
+
+impl RecentFiles {
+    pub fn load() -> RecentFiles {
+        let paths = file()
+            .and_then(|file| std::fs::read_to_string(file).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+
+        RecentFiles { paths }
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Moves `path` to the top of the list and writes the list out.
+    pub fn push(&mut self, path: &Path) {
+        self.paths.retain(|recent| recent != path);
+        self.paths.insert(0, path.to_path_buf());
+        self.paths.truncate(MAX_RECENT);
+
+        if let Err(error) = self.save() {
+            eprintln!("Cannot remember the recent files: {}", error);
+        }
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let Some(file) = file() else {
+            return Ok(());
+        };
+
+        if let Some(dir) = file.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        std::fs::write(file, serde_json::to_string_pretty(&self.paths)?)
+    }
+}
+
+fn file() -> Option<PathBuf> {
+    todo_core::project_dirs().map(|project_dirs| project_dirs.config_dir().join("recent.json"))
+}
@@ -0,0 +1,134 @@
+use iced::{button, container, Background, Color, Vector};
+
+/// Chip colours, picked for a tag by hashing its name.
+const TAG_COLORS: [[f32; 3]; 8] = [
+    [0.85, 0.33, 0.31],
+    [0.91, 0.56, 0.18],
+    [0.72, 0.62, 0.10],
+    [0.36, 0.66, 0.36],
+    [0.18, 0.62, 0.66],
+    [0.26, 0.52, 0.84],
+    [0.50, 0.40, 0.80],
+    [0.80, 0.38, 0.64],
+];
+
+pub enum Button {
+    FilterActive,
+    FilterSelected,
+    Icon,
+    Destructive,
+    /// A tag chip; `selected` while it filters the list.
+    Tag {
+        color: Color,
+        selected: bool,
+    },
+}
+
+/// The colour of a tag's chip, the same for a tag wherever it is shown.
+pub fn tag_color(tag: &str) -> Color {
+    let hash = tag.bytes().fold(0usize, |hash, byte| {
+        hash.wrapping_mul(31).wrapping_add(byte.into())
+    });
+    let [r, g, b] = TAG_COLORS[hash % TAG_COLORS.len()];
+
+    Color::from_rgb(r, g, b)
+}
+
+impl button::StyleSheet for Button {
+    fn active(&self) -> button::Style {
+        match self {
+            Button::FilterActive => button::Style::default(),
+            Button::FilterSelected => button::Style {
+                background: Some(Background::Color(Color::from_rgb(0.2, 0.2, 0.7))),
+                border_radius: 10.0,
+                text_color: Color::WHITE,
+                ..button::Style::default()
+            },
+            Button::Icon => button::Style {
+                text_color: Color::from_rgb(0.5, 0.5, 0.5),
+                ..button::Style::default()
+            },
+            Button::Destructive => button::Style {
+                background: Some(Background::Color(Color::from_rgb(0.8, 0.2, 0.2))),
+                border_radius: 5.0,
+                text_color: Color::WHITE,
+                shadow_offset: Vector::new(1.0, 1.0),
+                ..button::Style::default()
+            },
+            Button::Tag { color, selected } => button::Style {
+                background: Some(Background::Color(if *selected {
+                    *color
+                } else {
+                    Color { a: 0.15, ..*color }
+                })),
+                border_radius: 10.0,
+                border_width: 1.0,
+                border_color: *color,
+                text_color: if *selected { Color::WHITE } else { *color },
+                ..button::Style::default()
+            },
+        }
+    }
+
+    fn hovered(&self) -> button::Style {
+        let active = self.active();
+
+        button::Style {
+            text_color: match self {
+                Button::Icon => Color::from_rgb(0.2, 0.2, 0.7),
+                Button::FilterActive => Color::from_rgb(0.2, 0.2, 0.7),
+                _ => active.text_color,
+            },
+            shadow_offset: active.shadow_offset + Vector::new(0.0, 1.0),
+            ..active
+        }
+    }
+}
+
+pub enum Container {
+    Overdue,
+    Error,
+    Toast,
+    Badge,
+    Preview,
+}
+
+impl container::StyleSheet for Container {
+    fn style(&self) -> container::Style {
+        match self {
+            Container::Overdue => container::Style {
+                background: Some(Background::Color(Color::from_rgb(1.0, 0.93, 0.93))),
+                border_radius: 5.0,
+                border_width: 1.0,
+                border_color: Color::from_rgb(0.8, 0.2, 0.2),
+                ..container::Style::default()
+            },
+            Container::Error => container::Style {
+                background: Some(Background::Color(Color::from_rgb(1.0, 0.9, 0.9))),
+                border_radius: 5.0,
+                border_width: 2.0,
+                border_color: Color::from_rgb(0.8, 0.2, 0.2),
+                ..container::Style::default()
+            },
+            Container::Toast => container::Style {
+                text_color: Some(Color::WHITE),
+                background: Some(Background::Color(Color::from_rgb(0.25, 0.25, 0.25))),
+                border_radius: 5.0,
+                ..container::Style::default()
+            },
+            Container::Preview => container::Style {
+                background: Some(Background::Color(Color::from_rgb(0.96, 0.96, 0.96))),
+                border_radius: 5.0,
+                border_width: 1.0,
+                border_color: Color::from_rgb(0.85, 0.85, 0.85),
+                ..container::Style::default()
+            },
+            Container::Badge => container::Style {
+                text_color: Some(Color::WHITE),
+                background: Some(Background::Color(Color::from_rgb(0.8, 0.2, 0.2))),
+                border_radius: 3.0,
+                ..container::Style::default()
+            },
+        }
+    }
+}
@@ -0,0 +1,849 @@
+use crate::files::{FileKind, Files};
+use crate::icons::Icons;
+use crate::text_preview::{self, TextPreviews};
+use crate::thumbnails::{self, Thumbnails};
+use crate::{delete_icon, edit_icon, style};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use iced::button::{self, Button};
+use iced::pick_list::{self, PickList};
+use iced::text_input::{self, TextInput};
+use iced::{Align, Checkbox, Column, Container, Element, Image, Length, Radio, Row, Space, Text};
+use std::fmt;
+use std::path::PathBuf;
+use todo_core::{
+    parse_description, parse_due, Attachment, Importance, ListId, Target, Task, TaskList,
+};
+
+const DUE_FORMAT: &str = "%Y/%m/%d";
+
+const CREATED_FORMAT: &str = "%Y/%m/%d %H:%M";
+
+/// Widget state for a single task row, kept next to the task it renders.
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum TaskState {
+    Idle {
+        edit_button: button::State,
+        /// Makes the task the one Shift-dropped files are attached to.
+        target_button: button::State,
+        /// One set per attachment, grown and shrunk as the task is rendered.
+        attachment_buttons: Vec<AttachmentButtons>,
+        /// The delete button of each subtask.
+        subtask_buttons: Vec<button::State>,
+        /// One chip per tag, in the order of the task's tags.
+        tag_buttons: Vec<button::State>,
+        subtask_input: text_input::State,
+        subtask_value: String,
+        move_up_button: button::State,
+        move_down_button: button::State,
+        /// Why the last attachment could not be opened.
+        error: Option<String>,
+    },
+    /// Works on a copy of the task. Only the fields changed in it are
+    /// applied to the stored task when the edition is finished, so changes
+    /// made to the task meanwhile are kept.
+    Editing {
+        draft: Task,
+        /// The task as it was when the edition began.
+        original: Task,
+        text_input: text_input::State,
+        browse_button: button::State,
+        remove_buttons: Vec<button::State>,
+        tag_buttons: Vec<button::State>,
+        list_picker: pick_list::State<ListChoice>,
+        /// The lists the task can be moved to, as of when the edition began.
+        lists: Vec<ListChoice>,
+        due_input: text_input::State,
+        due_value: String,
+        created_input: text_input::State,
+        created_value: String,
+        error: Option<&'static str>,
+        cancel_button: button::State,
+        done_button: button::State,
+        delete_button: button::State,
+    },
+}
+
+/// A list offered in the editor's list picker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListChoice {
+    id: ListId,
+    name: String,
+}
+
+impl fmt::Display for ListChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AttachmentButtons {
+    open: button::State,
+    preview: button::State,
+    locate: button::State,
+    expand: button::State,
+    /// Whether the text preview is shown below the attachment.
+    expanded: bool,
+}
+
+impl Default for TaskState {
+    fn default() -> Self {
+        TaskState::Idle {
+            edit_button: button::State::new(),
+            target_button: button::State::new(),
+            attachment_buttons: Vec::new(),
+            subtask_buttons: Vec::new(),
+            tag_buttons: Vec::new(),
+            subtask_input: text_input::State::new(),
+            subtask_value: String::new(),
+            move_up_button: button::State::new(),
+            move_down_button: button::State::new(),
+            error: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum TaskMessage {
+    Completed(bool),
+    Edit,
+    DescriptionEdited(String),
+    ImportanceEdited(Importance),
+    BrowseFile,
+    /// The file picked in the dialog, `None` if it was dismissed.
+    FileChosen(Option<PathBuf>),
+    RemoveAttachment(usize),
+    DueEdited(String),
+    CreatedEdited(String),
+    FinishEdition,
+    CancelEdition,
+    Delete,
+    MoveUp,
+    MoveDown,
+    StartProcess(Target),
+    /// Asks where the missing file at this path went.
+    Locate(PathBuf),
+    /// The new place of a missing file, `None` if the dialog was dismissed.
+    Located(PathBuf, Option<PathBuf>),
+    /// Shows the image at this path in the preview pane.
+    Preview(PathBuf),
+    /// Shows or hides the text preview of the attachment at this index.
+    ToggleText(usize, PathBuf),
+    SubtaskCompleted(usize, bool),
+    SubtaskInputChanged(String),
+    AddSubtask,
+    DeleteSubtask(usize),
+    /// Filters the list by this tag, or stops filtering by it.
+    TagSelected(String),
+    /// Makes the task the drop target, or stops it being one.
+    ToggleDropTarget,
+    RemoveTag(String),
+    ListEdited(ListChoice),
+}
+
+impl TaskState {
+    /// Starts editing a copy of `task`, which may be moved to any of the
+    /// unarchived `lists`.
+    pub fn edit(&mut self, task: &Task, lists: &[TaskList]) {
+        *self = TaskState::Editing {
+            draft: task.clone(),
+            original: task.clone(),
+            text_input: text_input::State::focused(),
+            browse_button: button::State::new(),
+            remove_buttons: vec![button::State::new(); task.attachments.len()],
+            tag_buttons: vec![button::State::new(); task.tags.len()],
+            list_picker: pick_list::State::default(),
+            lists: lists
+                .iter()
+                .filter(|list| !list.archived || list.id == task.list)
+                .map(|list| ListChoice {
+                    id: list.id,
+                    name: list.name.clone(),
+                })
+                .collect(),
+            due_input: text_input::State::new(),
+            due_value: task
+                .due
+                .map(|due| due.format(DUE_FORMAT).to_string())
+                .unwrap_or_default(),
+            created_input: text_input::State::new(),
+            created_value: task.created_at.format(CREATED_FORMAT).to_string(),
+            error: None,
+            cancel_button: button::State::new(),
+            done_button: button::State::new(),
+            delete_button: button::State::new(),
+        };
+    }
+
+    pub fn is_editing(&self) -> bool {
+        matches!(self, TaskState::Editing { .. })
+    }
+
+    /// Applies a change of one field to the draft.
+    pub fn edit_field(&mut self, message: TaskMessage) {
+        if let TaskState::Editing {
+            draft,
+            due_value,
+            created_value,
+            error,
+            ..
+        } = self
+        {
+            match message {
+                TaskMessage::DescriptionEdited(description) => draft.description = description,
+                TaskMessage::ImportanceEdited(importance) => draft.importance = importance,
+                TaskMessage::FileChosen(Some(path)) => {
+                    draft.attachments.push(Attachment::new(path))
+                }
+                TaskMessage::RemoveAttachment(index) if index < draft.attachments.len() => {
+                    draft.attachments.remove(index);
+                }
+                TaskMessage::RemoveTag(tag) => {
+                    draft.tags.remove(&tag);
+                }
+                TaskMessage::ListEdited(choice) => draft.list = choice.id,
+                TaskMessage::DueEdited(value) => *due_value = value,
+                TaskMessage::CreatedEdited(value) => *created_value = value,
+                _ => return,
+            }
+
+            *error = None;
+        }
+    }
+
+    /// `current` with the fields changed in the editor, or `None` with an
+    /// error shown in the editor while a field does not hold a valid value.
+    pub fn edited(&mut self, current: &Task) -> Option<Task> {
+        let TaskState::Editing {
+            draft,
+            original,
+            due_value,
+            created_value,
+            error,
+            ..
+        } = self
+        else {
+            return None;
+        };
+
+        let due = if due_value.trim().is_empty() {
+            None
+        } else if let Some(due) = parse_due(due_value) {
+            Some(due)
+        } else {
+            *error = Some("期限は YYYY/MM/DD の形式で入力してください");
+            return None;
+        };
+
+        // Minutes are all the field shows; keep the exact time unless it was
+        // actually changed.
+        let created_at = if *created_value == draft.created_at.format(CREATED_FORMAT).to_string() {
+            Some(draft.created_at)
+        } else {
+            parse_created(created_value)
+        };
+
+        let Some(created_at) = created_at else {
+            *error = Some("作成日時は YYYY/MM/DD HH:MM の形式で入力してください");
+            return None;
+        };
+
+        // Tags and links typed into the description are added to the
+        // task's, as `TaskStore::edit` does.
+        let typed = parse_description(&draft.description);
+
+        if typed.text.is_empty() {
+            *error = Some("説明を入力してください");
+            return None;
+        }
+
+        let mut task = current.clone();
+
+        if draft.description != original.description {
+            task.description = typed.text;
+        }
+        if draft.importance != original.importance {
+            task.importance = draft.importance;
+        }
+        if draft.list != original.list {
+            task.list = draft.list;
+        }
+        if draft.tags != original.tags {
+            task.tags = draft.tags.clone();
+        }
+        if draft.attachments != original.attachments {
+            task.attachments = draft.attachments.clone();
+        }
+        if due != original.due {
+            task.due = due;
+        }
+        if created_at != original.created_at {
+            task.created_at = created_at;
+        }
+
+        task.tags.extend(typed.tags);
+
+        for link in typed.links {
+            if !task.attachments.contains(&link) {
+                task.attachments.push(link);
+            }
+        }
+
+        Some(task)
+    }
+
+    pub fn finish_edition(&mut self) {
+        *self = TaskState::default();
+    }
+
+    /// Shows or hides the text preview of an attachment, returning whether
+    /// it is now shown.
+    pub fn toggle_text(&mut self, index: usize) -> bool {
+        match self {
+            TaskState::Idle {
+                attachment_buttons, ..
+            } => match attachment_buttons.get_mut(index) {
+                Some(buttons) => {
+                    buttons.expanded = !buttons.expanded;
+                    buttons.expanded
+                }
+                None => false,
+            },
+            TaskState::Editing { .. } => false,
+        }
+    }
+
+    /// Empties the new subtask field, returning what was typed into it.
+    pub fn take_subtask(&mut self) -> Option<String> {
+        match self {
+            TaskState::Idle { subtask_value, .. } => {
+                let description = std::mem::take(subtask_value).trim().to_string();
+
+                (!description.is_empty()).then_some(description)
+            }
+            TaskState::Editing { .. } => None,
+        }
+    }
+
+    pub fn set_subtask_value(&mut self, value: String) {
+        if let TaskState::Idle { subtask_value, .. } = self {
+            *subtask_value = value;
+        }
+    }
+
+    /// Shows why an attachment could not be opened, or clears the message.
+    pub fn set_error(&mut self, message: Option<String>) {
+        if let TaskState::Idle { error, .. } = self {
+            *error = message;
+        }
+    }
+
+    /// Renders the task row; `movable` adds the buttons for manual ordering,
+    /// `targeted` marks the task Shift-dropped files are attached to, and
+    /// attachments found missing at the last check of `files` are flagged.
+    #[allow(clippy::too_many_arguments)]
+    pub fn view<'a>(
+        &'a mut self,
+        task: &'a Task,
+        movable: bool,
+        targeted: bool,
+        files: &Files,
+        icons: &Icons,
+        thumbnails: &Thumbnails,
+        texts: &TextPreviews,
+    ) -> Element<'a, TaskMessage> {
+        match self {
+            TaskState::Idle {
+                edit_button,
+                target_button,
+                attachment_buttons,
+                subtask_buttons,
+                tag_buttons,
+                subtask_input,
+                subtask_value,
+                move_up_button,
+                move_down_button,
+                error,
+            } => {
+                let checkbox =
+                    Checkbox::new(task.completed, &task.description, TaskMessage::Completed)
+                        .width(Length::Fill);
+
+                subtask_buttons.resize_with(task.subtasks.len(), Default::default);
+
+                let subtasks = task
+                    .subtasks
+                    .iter()
+                    .zip(subtask_buttons)
+                    .enumerate()
+                    .fold(
+                        Column::new().spacing(5),
+                        |column, (index, (subtask, delete_button))| {
+                            column.push(
+                                Row::new()
+                                    .spacing(10)
+                                    .align_items(Align::Center)
+                                    .push(
+                                        Checkbox::new(
+                                            subtask.completed,
+                                            &subtask.description,
+                                            move |completed| {
+                                                TaskMessage::SubtaskCompleted(index, completed)
+                                            },
+                                        )
+                                        .width(Length::Fill),
+                                    )
+                                    .push(
+                                        Button::new(delete_button, Text::new("×").size(16))
+                                            .on_press(TaskMessage::DeleteSubtask(index))
+                                            .padding(5)
+                                            .style(style::Button::Icon),
+                                    ),
+                            )
+                        },
+                    )
+                    .push(
+                        TextInput::new(
+                            subtask_input,
+                            "Add a subtask",
+                            subtask_value,
+                            TaskMessage::SubtaskInputChanged,
+                        )
+                        .on_submit(TaskMessage::AddSubtask)
+                        .padding(5)
+                        .size(16),
+                    );
+
+                // Indented under the task they belong to.
+                let subtasks = Row::new()
+                    .push(Space::with_width(Length::Units(30)))
+                    .push(subtasks);
+
+                let important = Text::new(task.importance).width(Length::Fill);
+
+                tag_buttons.resize_with(task.tags.len(), Default::default);
+
+                let tags = task.tags.iter().zip(tag_buttons).fold(
+                    Row::new().spacing(5),
+                    |row, (tag, state)| {
+                        row.push(
+                            tag_chip(state, tag, format!("#{}", tag), false)
+                                .on_press(TaskMessage::TagSelected(tag.clone())),
+                        )
+                    },
+                );
+
+                attachment_buttons.resize_with(task.attachments.len(), Default::default);
+
+                let attachments = task
+                    .attachments
+                    .iter()
+                    .zip(attachment_buttons)
+                    .enumerate()
+                    .fold(
+                        Column::new().spacing(5),
+                        |column, (index, (attachment, buttons))| {
+                            let mut row = Row::new().spacing(10).align_items(Align::Center);
+
+                            let thumbnail = attachment.path().and_then(|path| {
+                                Some((path, thumbnails.get(path)?.handle.as_ref()?))
+                            });
+
+                            if let Some((path, thumbnail)) = thumbnail {
+                                let size = Length::Units(thumbnails::SIZE as u16);
+
+                                row = row.push(
+                                    Button::new(
+                                        &mut buttons.preview,
+                                        Image::new(thumbnail.clone()).width(size).height(size),
+                                    )
+                                    .on_press(TaskMessage::Preview(path.to_path_buf()))
+                                    .style(style::Button::Icon),
+                                );
+                            }
+
+                            row = row.push(
+                                Button::new(
+                                    &mut buttons.open,
+                                    Row::new()
+                                        .spacing(5)
+                                        .align_items(Align::Center)
+                                        .push(attachment_icon(icons, files, attachment))
+                                        .push(Text::new(&attachment.name)),
+                                )
+                                .on_press(TaskMessage::StartProcess(attachment.target.clone()))
+                                .style(style::Button::Icon),
+                            );
+
+                            if let Some(path) = attachment
+                                .path()
+                                .filter(|path| files.get(*path) == Some(&FileKind::Missing))
+                            {
+                                row = row
+                                    .push(
+                                        Container::new(Text::new("missing").size(14))
+                                            .padding(3)
+                                            .style(style::Container::Badge),
+                                    )
+                                    .push(
+                                        Button::new(
+                                            &mut buttons.locate,
+                                            Text::new("Locate…").size(16),
+                                        )
+                                        .on_press(TaskMessage::Locate(path.to_path_buf()))
+                                        .padding(8)
+                                        .style(style::Button::FilterActive),
+                                    );
+                            }
+
+                            let text = attachment
+                                .path()
+                                .filter(|path| text_preview::is_text(path, files.get(*path)));
+
+                            if let Some(path) = text {
+                                row = row.push(
+                                    Button::new(
+                                        &mut buttons.expand,
+                                        Text::new(if buttons.expanded { "▾" } else { "▸" })
+                                            .size(16),
+                                    )
+                                    .on_press(TaskMessage::ToggleText(index, path.to_path_buf()))
+                                    .padding(8)
+                                    .style(style::Button::Icon),
+                                );
+                            }
+
+                            let column = column.push(row);
+
+                            match text {
+                                Some(path) if buttons.expanded => {
+                                    column.push(text_preview::view(texts.get(path)))
+                                }
+                                _ => column,
+                            }
+                        },
+                    );
+
+                let error = Text::new(error.as_deref().unwrap_or_default())
+                    .size(16)
+                    .color([0.8, 0.2, 0.2]);
+
+                let mut datetime = task.created_at.format(" Added %Y/%m/%d %H:%M").to_string();
+
+                if let Some(completed_at) = task.completed_at {
+                    datetime += &completed_at
+                        .format("  Completed %Y/%m/%d %H:%M")
+                        .to_string();
+                }
+
+                let datetime_text = Text::new(datetime);
+
+                let overdue = task.is_overdue(Local::today().naive_local());
+
+                let due_text = match task.due {
+                    Some(due) if overdue => {
+                        Text::new(format!("期限 {} (期限切れ)", due.format(DUE_FORMAT)))
+                            .color([0.8, 0.2, 0.2])
+                    }
+                    Some(due) => Text::new(format!("期限 {}", due.format(DUE_FORMAT))),
+                    None => Text::new(""),
+                };
+
+                let mut header = Row::new()
+                    .spacing(20)
+                    .align_items(Align::Center)
+                    .push(checkbox);
+
+                if let Some((done, total)) = task.progress() {
+                    header = header.push(Text::new(format!("{}/{}", done, total)).size(16));
+                }
+
+                if movable {
+                    header = header
+                        .push(
+                            Button::new(move_up_button, Text::new("▲").size(16))
+                                .on_press(TaskMessage::MoveUp)
+                                .padding(10)
+                                .style(style::Button::Icon),
+                        )
+                        .push(
+                            Button::new(move_down_button, Text::new("▼").size(16))
+                                .on_press(TaskMessage::MoveDown)
+                                .padding(10)
+                                .style(style::Button::Icon),
+                        );
+                }
+
+                let header = header
+                    .push(
+                        Button::new(target_button, Text::new("Drop here").size(16))
+                            .on_press(TaskMessage::ToggleDropTarget)
+                            .padding(8)
+                            .style(if targeted {
+                                style::Button::FilterSelected
+                            } else {
+                                style::Button::Icon
+                            }),
+                    )
+                    .push(
+                        Button::new(edit_button, edit_icon())
+                            .on_press(TaskMessage::Edit)
+                            .padding(10)
+                            .style(style::Button::Icon),
+                    );
+
+                let hint = Text::new(if targeted {
+                    "Hold Shift while dropping files to attach them here"
+                } else {
+                    ""
+                })
+                .size(14);
+
+                let content = Column::new()
+                    .push(header)
+                    .push(hint)
+                    .push(tags)
+                    .push(subtasks)
+                    .push(attachments)
+                    .push(error)
+                    .push(important)
+                    .push(due_text)
+                    .push(Space::new(Length::Fill, Length::Units(5)))
+                    .push(datetime_text)
+                    .align_items(Align::End);
+
+                if overdue {
+                    Container::new(content)
+                        .padding(5)
+                        .style(style::Container::Overdue)
+                        .into()
+                } else {
+                    content.into()
+                }
+            }
+            TaskState::Editing {
+                draft,
+                text_input,
+                browse_button,
+                remove_buttons,
+                tag_buttons,
+                list_picker,
+                lists,
+                due_input,
+                due_value,
+                created_input,
+                created_value,
+                error,
+                cancel_button,
+                done_button,
+                delete_button,
+                ..
+            } => {
+                let text_input = TextInput::new(
+                    text_input,
+                    "Describe your task...",
+                    &draft.description,
+                    TaskMessage::DescriptionEdited,
+                )
+                .on_submit(TaskMessage::FinishEdition)
+                .padding(10);
+
+                let current_list = lists.iter().find(|list| list.id == draft.list).cloned();
+
+                let list_picker = PickList::new(
+                    list_picker,
+                    &lists[..],
+                    current_list,
+                    TaskMessage::ListEdited,
+                )
+                .text_size(16)
+                .padding(8);
+
+                let importance = Importance::all().iter().cloned().fold(
+                    Row::new().spacing(5).width(Length::Fill),
+                    |choices, importance| {
+                        choices.push(
+                            Radio::new(
+                                importance,
+                                importance,
+                                Some(draft.importance),
+                                TaskMessage::ImportanceEdited,
+                            )
+                            .text_size(16)
+                            .size(16)
+                            .spacing(5),
+                        )
+                    },
+                );
+
+                remove_buttons.resize_with(draft.attachments.len(), Default::default);
+
+                let attachments = draft
+                    .attachments
+                    .iter()
+                    .zip(remove_buttons)
+                    .enumerate()
+                    .fold(
+                        Column::new().spacing(5),
+                        |column, (index, (attachment, state))| {
+                            column.push(
+                                Row::new()
+                                    .spacing(10)
+                                    .align_items(Align::Center)
+                                    .push(attachment_icon(icons, files, attachment))
+                                    .push(
+                                        Text::new(attachment.target.location().to_string_lossy())
+                                            .size(16)
+                                            .width(Length::Fill),
+                                    )
+                                    .push(
+                                        Button::new(state, Text::new("Remove").size(16))
+                                            .on_press(TaskMessage::RemoveAttachment(index))
+                                            .padding(8)
+                                            .style(style::Button::FilterActive),
+                                    ),
+                            )
+                        },
+                    )
+                    .push(
+                        Button::new(browse_button, Text::new("Attach…").size(16))
+                            .on_press(TaskMessage::BrowseFile)
+                            .padding(8)
+                            .style(style::Button::FilterActive),
+                    );
+
+                tag_buttons.resize_with(draft.tags.len(), Default::default);
+
+                let tags = draft.tags.iter().zip(tag_buttons).fold(
+                    Row::new()
+                        .spacing(5)
+                        .align_items(Align::Center)
+                        .push(Text::new("Tags").size(16)),
+                    |row, (tag, state)| {
+                        row.push(
+                            tag_chip(state, tag, format!("#{} ×", tag), false)
+                                .on_press(TaskMessage::RemoveTag(tag.clone())),
+                        )
+                    },
+                );
+
+                let tags = tags.push(
+                    Text::new("#tag in the description adds one")
+                        .size(14)
+                        .color([0.5, 0.5, 0.5]),
+                );
+
+                let due_input = TextInput::new(
+                    due_input,
+                    "期限 YYYY/MM/DD",
+                    due_value,
+                    TaskMessage::DueEdited,
+                )
+                .on_submit(TaskMessage::FinishEdition)
+                .width(Length::Units(160))
+                .padding(10);
+
+                let created_input = TextInput::new(
+                    created_input,
+                    "YYYY/MM/DD HH:MM",
+                    created_value,
+                    TaskMessage::CreatedEdited,
+                )
+                .on_submit(TaskMessage::FinishEdition)
+                .width(Length::Units(200))
+                .padding(10);
+
+                let dates = Row::new()
+                    .spacing(10)
+                    .align_items(Align::Center)
+                    .push(Text::new("Due").size(16))
+                    .push(due_input)
+                    .push(Text::new("Added").size(16))
+                    .push(created_input);
+
+                let error = Text::new(error.unwrap_or_default())
+                    .size(16)
+                    .color([0.8, 0.2, 0.2])
+                    .width(Length::Fill);
+
+                let actions = Row::new()
+                    .spacing(10)
+                    .align_items(Align::Center)
+                    .push(error)
+                    .push(
+                        Button::new(
+                            delete_button,
+                            Row::new()
+                                .spacing(10)
+                                .push(delete_icon())
+                                .push(Text::new("Delete")),
+                        )
+                        .on_press(TaskMessage::Delete)
+                        .padding(10)
+                        .style(style::Button::Destructive),
+                    )
+                    .push(
+                        Button::new(cancel_button, Text::new("Cancel"))
+                            .on_press(TaskMessage::CancelEdition)
+                            .padding(10)
+                            .style(style::Button::FilterActive),
+                    )
+                    .push(
+                        Button::new(done_button, Text::new("Done"))
+                            .on_press(TaskMessage::FinishEdition)
+                            .padding(10)
+                            .style(style::Button::FilterSelected),
+                    );
+
+                Column::new()
+                    .spacing(10)
+                    .push(text_input)
+                    .push(
+                        Row::new()
+                            .spacing(10)
+                            .align_items(Align::Center)
+                            .push(importance)
+                            .push(Text::new("List").size(16))
+                            .push(list_picker),
+                    )
+                    .push(tags)
+                    .push(attachments)
+                    .push(dates)
+                    .push(actions)
+                    .into()
+            }
+        }
+    }
+}
+
+/// A chip showing a tag in the same colour wherever it appears, filled in
+/// while `selected`.
+pub fn tag_chip<'a, Message: Clone>(
+    state: &'a mut button::State,
+    tag: &str,
+    label: String,
+    selected: bool,
+) -> Button<'a, Message> {
+    Button::new(state, Text::new(label).size(14))
+        .padding(4)
+        .style(style::Button::Tag {
+            color: style::tag_color(tag),
+            selected,
+        })
+}
+
+/// Parses a creation time typed as `2021/12/24 18:30`.
+fn parse_created(input: &str) -> Option<DateTime<Local>> {
+    let date = NaiveDateTime::parse_from_str(input.trim(), CREATED_FORMAT).ok()?;
+
+    Local.from_local_datetime(&date).earliest()
+}
+
+fn attachment_icon(icons: &Icons, files: &Files, attachment: &Attachment) -> Image {
+    let kind = attachment.path().and_then(|path| files.get(path));
+
+    Image::new(icons.get(attachment, kind))
+        .width(Length::Units(30))
+        .height(Length::Units(30))
+}
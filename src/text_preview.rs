@@ -0,0 +1,191 @@
+//! The first lines of text attachments, shown inside the task row. Files
+//! are decoded as UTF-8 or, failing that, as Shift_JIS; CSV and TSV files
+//! are shown as a table.
+
+use crate::files::FileKind;
+use crate::style;
+use encoding_rs::SHIFT_JIS;
+use iced::{Column, Container, Element, Length, Row, Text};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Only this much of a file is read, however large it is.
+const MAX_BYTES: u64 = 64 * 1024;
+
+const MAX_LINES: usize = 20;
+
+const MAX_COLUMNS: usize = 8;
+
+/// Extensions shown as text even when the content was not sniffed yet.
+const TEXT_EXTENSIONS: [&str; 5] = ["txt", "log", "md", "csv", "tsv"];
+
+#[derive(Debug, Clone)]
+pub struct TextPreview {
+    pub content: Content,
+    pub encoding: &'static str,
+    /// Whether there is more to the file than what is shown.
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum Content {
+    Lines(Vec<String>),
+    Table(Vec<Vec<String>>),
+}
+
+/// Loaded previews, by path; an error explains why one could not be read.
+pub type TextPreviews = HashMap<PathBuf, Result<TextPreview, String>>;
+
+pub fn is_text(path: &Path, kind: Option<&FileKind>) -> bool {
+    match kind {
+        Some(FileKind::File(Some("text/plain"))) => true,
+        Some(FileKind::Missing | FileKind::Directory) => false,
+        _ => extension(path).is_some_and(|extension| TEXT_EXTENSIONS.contains(&extension.as_str())),
+    }
+}
+
+pub async fn load(path: PathBuf) -> (PathBuf, Result<TextPreview, String>) {
+    let preview = read(&path).map_err(|error| error.to_string());
+
+    (path, preview)
+}
+
+fn read(path: &Path) -> std::io::Result<TextPreview> {
+    let file = File::open(path)?;
+    let length = file.metadata()?.len();
+
+    let mut bytes = Vec::new();
+    file.take(MAX_BYTES).read_to_end(&mut bytes)?;
+
+    let (text, encoding) = decode(&bytes, length > MAX_BYTES);
+    let mut lines = text.lines();
+    let shown = lines.by_ref().take(MAX_LINES).collect::<Vec<_>>();
+    let truncated = length > MAX_BYTES || lines.next().is_some();
+
+    let content = match delimiter(path) {
+        Some(delimiter) => Content::Table(
+            shown
+                .iter()
+                .map(|line| {
+                    let mut fields = split_fields(line, delimiter);
+                    fields.truncate(MAX_COLUMNS);
+                    fields
+                })
+                .collect(),
+        ),
+        None => Content::Lines(shown.into_iter().map(String::from).collect()),
+    };
+
+    Ok(TextPreview {
+        content,
+        encoding,
+        truncated,
+    })
+}
+
+/// Decodes UTF-8 when the bytes are valid as such, and Shift_JIS otherwise;
+/// `cut` tells that the bytes may end in the middle of a character.
+fn decode(bytes: &[u8], cut: bool) -> (String, &'static str) {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+
+    match std::str::from_utf8(bytes) {
+        Ok(text) => (text.to_string(), "UTF-8"),
+        // Only the last character was cut off by the size cap.
+        Err(error) if cut && error.error_len().is_none() => (
+            String::from_utf8_lossy(&bytes[..error.valid_up_to()]).into_owned(),
+            "UTF-8",
+        ),
+        Err(_) => {
+            let (text, _) = SHIFT_JIS.decode_without_bom_handling(bytes);
+
+            (text.into_owned(), "Shift_JIS")
+        }
+    }
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+}
+
+fn delimiter(path: &Path) -> Option<char> {
+    match extension(path)?.as_str() {
+        "csv" => Some(','),
+        "tsv" => Some('\t'),
+        _ => None,
+    }
+}
+
+/// Splits one line of a CSV or TSV file, honouring double quotes.
+fn split_fields(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut().expect("there is always a field");
+
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields.push(String::new()),
+            c => field.push(c),
+        }
+    }
+
+    fields
+}
+
+/// Renders a preview, or a notice while it is loading (`None`).
+pub fn view<'a, Message: 'a>(
+    preview: Option<&Result<TextPreview, String>>,
+) -> Element<'a, Message> {
+    let content: Element<_> = match preview {
+        None => Text::new("読み込み中…").size(14).into(),
+        Some(Err(error)) => Text::new(format!("読み込めませんでした: {}", error))
+            .size(14)
+            .color([0.8, 0.2, 0.2])
+            .into(),
+        Some(Ok(preview)) => {
+            let body: Element<_> = match &preview.content {
+                Content::Lines(lines) => lines
+                    .iter()
+                    .fold(Column::new(), |column, line| {
+                        column.push(Text::new(line.as_str()).size(14))
+                    })
+                    .into(),
+                Content::Table(rows) => rows
+                    .iter()
+                    .fold(Column::new().spacing(2), |column, fields| {
+                        column.push(fields.iter().fold(Row::new().spacing(10), |row, field| {
+                            row.push(Text::new(field.as_str()).size(14).width(Length::Fill))
+                        }))
+                    })
+                    .into(),
+            };
+
+            let mut footer = String::from(preview.encoding);
+
+            if preview.truncated {
+                footer += " …";
+            }
+
+            Column::new()
+                .spacing(5)
+                .push(body)
+                .push(Text::new(footer).size(12).color([0.5, 0.5, 0.5]))
+                .into()
+        }
+    };
+
+    Container::new(content)
+        .width(Length::Fill)
+        .padding(10)
+        .style(style::Container::Preview)
+        .into()
+}
@@ -0,0 +1,144 @@
+//! Small previews of image attachments, cached on disk so each image is
+//! only decoded again after it changes.
+
+use crate::watch::Stamp;
+use iced::image::Handle;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Longest side of a thumbnail, in pixels.
+pub const SIZE: u32 = 64;
+
+/// How long a cached thumbnail no attachment uses is kept, e.g. for the
+/// images of another todo file.
+const KEEP_UNUSED: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Image types a thumbnail is made for, by sniffed MIME type.
+const IMAGE_TYPES: [&str; 5] = [
+    "image/jpeg",
+    "image/png",
+    "image/gif",
+    "image/bmp",
+    "image/webp",
+];
+
+/// The thumbnail of one version of an image; `None` when it could not be
+/// made, so it is only tried again once the image changes.
+#[derive(Debug, Clone)]
+pub struct Thumbnail {
+    pub stamp: Option<Stamp>,
+    pub handle: Option<Handle>,
+}
+
+/// Generated thumbnails, by the path of the image they show.
+pub type Thumbnails = HashMap<PathBuf, Thumbnail>;
+
+pub fn is_image(mime_type: &str) -> bool {
+    IMAGE_TYPES.contains(&mime_type)
+}
+
+/// Makes the thumbnail of the image at `path`, or reuses the cached one
+/// while the image has not been modified since. Returns the version of the
+/// image it was made from.
+pub async fn generate(path: PathBuf) -> (PathBuf, Option<Stamp>, Option<PathBuf>) {
+    let stamp = Stamp::of(&path);
+    let thumbnail = stamp
+        .and_then(|stamp| cache_path(&path, stamp))
+        .and_then(|thumbnail| cached(&thumbnail).or_else(|| create(&path, thumbnail)));
+
+    (path, stamp, thumbnail)
+}
+
+/// Removes the cached thumbnails that none of the `images` uses and that
+/// were not used for [`KEEP_UNUSED`].
+pub async fn prune(images: Vec<PathBuf>) {
+    let Some(dir) = cache_dir() else {
+        return;
+    };
+
+    let in_use = images
+        .iter()
+        .filter_map(|image| cache_path(image, Stamp::of(image)?))
+        .collect::<HashSet<_>>();
+
+    let entries = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok);
+
+    for entry in entries {
+        let path = entry.path();
+        let unused_for = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+
+        if !in_use.contains(&path) && unused_for.is_some_and(|age| age > KEEP_UNUSED) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+fn cached(thumbnail: &Path) -> Option<PathBuf> {
+    if !thumbnail.is_file() {
+        return None;
+    }
+
+    // Marks it as used, for `prune`.
+    let _ = std::fs::File::options()
+        .write(true)
+        .open(thumbnail)
+        .and_then(|file| file.set_modified(SystemTime::now()));
+
+    Some(thumbnail.to_path_buf())
+}
+
+fn create(path: &Path, thumbnail: PathBuf) -> Option<PathBuf> {
+    std::fs::create_dir_all(thumbnail.parent()?).ok()?;
+
+    let image = match image::open(path) {
+        Ok(image) => image,
+        Err(error) => {
+            eprintln!("Cannot make a thumbnail of {}: {}", path.display(), error);
+            return None;
+        }
+    };
+
+    // Written aside first so a half-written file is never taken from the
+    // cache.
+    let temp = thumbnail.with_extension(format!("{}.tmp.png", std::process::id()));
+    image.thumbnail(SIZE, SIZE).save(&temp).ok()?;
+    std::fs::rename(&temp, &thumbnail).ok()?;
+
+    Some(thumbnail)
+}
+
+fn cache_dir() -> Option<PathBuf> {
+    Some(todo_core::project_dirs()?.cache_dir().join("thumbnails"))
+}
+
+/// Where the thumbnail of one version of the image at `path` is cached:
+/// named after the path, modification time and size, hashed the same way by
+/// every build so the cache outlives toolchain updates.
+fn cache_path(path: &Path, stamp: Stamp) -> Option<PathBuf> {
+    let (modified, len) = stamp.parts();
+
+    let hash = [
+        path.to_string_lossy().as_bytes(),
+        &modified.to_le_bytes(),
+        &len.to_le_bytes(),
+    ]
+    .into_iter()
+    .flatten()
+    .fold(FNV_OFFSET, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+    });
+
+    Some(cache_dir()?.join(format!("{:016x}.png", hash)))
+}
+
+/// 64-bit FNV-1a.
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
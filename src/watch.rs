@@ -0,0 +1,70 @@
+//! Notices when the todo file is changed by someone else: a hand edit,
+//! another instance or a sync tool. The file is looked at every
+//! [`INTERVAL`], and only read again once its size or modification time
+//! moved.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use todo_core::{LoadError, SavedState, Task, TaskList};
+
+pub const INTERVAL: Duration = Duration::from_secs(1);
+
+/// What identifies one version of the file without reading it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stamp {
+    modified: SystemTime,
+    len: u64,
+}
+
+impl Stamp {
+    /// `None` while there is no file at `path`.
+    pub fn of(path: &Path) -> Option<Stamp> {
+        let metadata = path.metadata().ok()?;
+
+        Some(Stamp {
+            modified: metadata.modified().ok()?,
+            len: metadata.len(),
+        })
+    }
+
+    /// The modification time, in nanoseconds since the epoch, and the size.
+    pub fn parts(&self) -> (u128, u64) {
+        let modified = self
+            .modified
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+
+        (modified.as_nanos(), self.len)
+    }
+}
+
+/// The file as last loaded or saved by the app: the common ancestor when
+/// both the app and the file have changed since.
+#[derive(Debug, Clone, Default)]
+pub struct OnDisk {
+    pub stamp: Option<Stamp>,
+    pub lists: Vec<TaskList>,
+    pub tasks: Vec<Task>,
+}
+
+impl OnDisk {
+    pub fn new(stamp: Option<Stamp>, saved: &SavedState) -> OnDisk {
+        OnDisk {
+            stamp,
+            lists: saved.lists.clone(),
+            tasks: saved.tasks.clone(),
+        }
+    }
+
+    /// Whether `saved` holds the same tasks and lists.
+    pub fn matches(&self, saved: &SavedState) -> bool {
+        self.tasks == saved.tasks && self.lists == saved.lists
+    }
+}
+
+/// Reads the file after it changed, with the stamp of the version read.
+pub async fn read(path: PathBuf) -> (Option<Stamp>, Result<SavedState, LoadError>) {
+    let stamp = Stamp::of(&path);
+
+    (stamp, SavedState::load_from(&path))
+}
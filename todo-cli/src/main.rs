@@ -0,0 +1,579 @@
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use todo_core::{
+    parse_description, parse_due, parse_tag, Attachment, Filter, Importance, ListId, SavedState,
+    Sort, TagFilter, TagMode, Task, TaskId, TaskStore,
+};
+
+/// Manage the tasks shown by the todo app from the command line.
+#[derive(Debug, Parser)]
+#[command(name = "ex_todo", version)]
+struct Cli {
+    /// Print machine readable JSON instead of a table
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Todo file to use; defaults to .ex_todo.json in the current directory
+    /// if there is one, and to the file the app uses otherwise
+    #[arg(long, global = true, value_name = "FILE")]
+    todo_file: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Add a new task; links in the description are attached to it and
+    /// #words become its tags
+    Add {
+        description: String,
+        /// low, normal or high
+        #[arg(long, short, default_value = "normal")]
+        importance: Importance,
+        /// File to attach to the task; may be repeated
+        #[arg(long, short)]
+        file: Vec<PathBuf>,
+        /// Due date, e.g. 2021/12/24
+        #[arg(long, short, value_parser = due_date)]
+        due: Option<NaiveDate>,
+        /// Name or id of the list to add the task to; defaults to the inbox
+        #[arg(long, short)]
+        list: Option<String>,
+    },
+    /// List tasks
+    List {
+        /// all, active, completed, overdue, today or this-week
+        #[arg(long, short, default_value = "all")]
+        filter: Filter,
+        /// Only show tasks whose description contains this text
+        #[arg(long, short, default_value = "")]
+        grep: String,
+        /// Only show tasks of these importances, e.g. high,normal
+        #[arg(long, short, value_delimiter = ',')]
+        importance: Vec<Importance>,
+        /// Only show tasks with these tags, e.g. client-a,review
+        #[arg(long, short, value_delimiter = ',', value_parser = tag)]
+        tag: Vec<String>,
+        /// Whether a task needs any or all of the tags given with --tag
+        #[arg(long, default_value = "any")]
+        tag_mode: TagMode,
+        /// Only show the tasks of this list, by name or id
+        #[arg(long, short)]
+        list: Option<String>,
+        /// manual, importance, created, due, completed or description;
+        /// defaults to the order chosen in the app
+        #[arg(long, short)]
+        sort: Option<Sort>,
+    },
+    /// Mark a task as completed
+    Done {
+        id: String,
+        /// Only check this subtask, counting from 1
+        #[arg(long)]
+        sub: Option<usize>,
+    },
+    /// Add a subtask to a task
+    Sub { id: String, description: String },
+    /// Replace the description of a task
+    Edit { id: String, description: String },
+    /// Delete a task
+    Rm { id: String },
+    /// Attach a file or an http(s), mailto or file:// link to a task
+    Attach { id: String, file: PathBuf },
+    /// Open the files attached to a task
+    Open { id: String },
+    /// Move a task to another list
+    Mv { id: String, list: String },
+    /// Show the task lists, or change them
+    Lists {
+        #[command(subcommand)]
+        action: Option<ListAction>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ListAction {
+    /// Create a list
+    Add { name: String },
+    /// Rename a list
+    Rename { list: String, name: String },
+    /// Hide a list from the app's switcher, keeping its tasks
+    Archive {
+        list: String,
+        /// Show the list again
+        #[arg(long)]
+        restore: bool,
+    },
+    /// Delete a list and every task in it
+    Rm { list: String },
+}
+
+/// Number of id characters shown in the table; any unique prefix is accepted.
+const SHORT_ID: usize = 8;
+
+const DATE_FORMAT: &str = "%Y/%m/%d %H:%M";
+
+const DUE_FORMAT: &str = "%Y/%m/%d";
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("ex_todo: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let path = cli.todo_file.clone().unwrap_or_else(SavedState::discover);
+    let mut saved = load(&path)?;
+    let mut store = TaskStore::with_lists(
+        std::mem::take(&mut saved.lists),
+        std::mem::take(&mut saved.tasks),
+    );
+
+    match cli.command {
+        Command::Add {
+            description,
+            importance,
+            file,
+            due,
+            list,
+        } => {
+            let list = match list {
+                Some(list) => resolve_list(&store, &list)?,
+                None => ListId::INBOX,
+            };
+            let typed = parse_description(&description);
+            let mut attachments = typed.links;
+            for file in &file {
+                attachments.push(attachment(file)?);
+            }
+            let id = store.add(Task {
+                tags: typed.tags,
+                list,
+                ..Task::new(typed.text, attachments, importance, due)
+            });
+            print_tasks(&store, [id], cli.json)?;
+            save(&path, saved, store)?;
+        }
+        Command::List {
+            filter,
+            grep,
+            importance,
+            tag,
+            tag_mode,
+            list,
+            sort,
+        } => {
+            let list = list.map(|list| resolve_list(&store, &list)).transpose()?;
+            let tags = TagFilter {
+                tags: tag.into_iter().collect(),
+                mode: tag_mode,
+            };
+            let ids = store
+                .sorted(
+                    list,
+                    filter,
+                    &grep,
+                    &importance,
+                    &tags,
+                    sort.unwrap_or(saved.sort),
+                )
+                .into_iter()
+                .map(|task| task.id)
+                .collect::<Vec<_>>();
+            print_tasks(&store, ids, cli.json)?;
+        }
+        Command::Done { id, sub: None } => {
+            let id = resolve(&store, &id)?;
+            store.complete(id, true);
+            print_tasks(&store, [id], cli.json)?;
+            save(&path, saved, store)?;
+        }
+        Command::Done { id, sub: Some(sub) } => {
+            let id = resolve(&store, &id)?;
+            let checked = sub
+                .checked_sub(1)
+                .is_some_and(|index| store.complete_subtask(id, index, true, saved.auto_complete));
+            if !checked {
+                return Err(format!("task {} has no subtask {}", short_id(id), sub).into());
+            }
+            print_tasks(&store, [id], cli.json)?;
+            save(&path, saved, store)?;
+        }
+        Command::Sub { id, description } => {
+            if description.is_empty() {
+                return Err("the description cannot be empty".into());
+            }
+            let id = resolve(&store, &id)?;
+            store.add_subtask(id, description);
+            print_tasks(&store, [id], cli.json)?;
+            save(&path, saved, store)?;
+        }
+        Command::Edit { id, description } => {
+            if description.is_empty() {
+                return Err("the description cannot be empty".into());
+            }
+            let id = resolve(&store, &id)?;
+            store.edit(id, &description);
+            print_tasks(&store, [id], cli.json)?;
+            save(&path, saved, store)?;
+        }
+        Command::Rm { id } => {
+            let id = resolve(&store, &id)?;
+            let task = store.delete(id).ok_or("the task disappeared")?;
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&task)?);
+            } else {
+                println!("Deleted {}: {}", short_id(id), task.description);
+            }
+            save(&path, saved, store)?;
+        }
+        Command::Attach { id, file } => {
+            let id = resolve(&store, &id)?;
+            store.attach(id, attachment(&file)?);
+            print_tasks(&store, [id], cli.json)?;
+            save(&path, saved, store)?;
+        }
+        Command::Open { id } => {
+            let task = store
+                .get(resolve(&store, &id)?)
+                .ok_or("the task disappeared")?;
+            if task.attachments.is_empty() {
+                return Err(format!("task {} has no attached file", short_id(task.id)).into());
+            }
+            for attachment in &task.attachments {
+                let location = attachment.target.location();
+                open::that(location)
+                    .map_err(|error| format!("{}: {}", location.to_string_lossy(), error))?;
+            }
+        }
+        Command::Mv { id, list } => {
+            let id = resolve(&store, &id)?;
+            let list = resolve_list(&store, &list)?;
+            store.move_to_list(id, list);
+            print_tasks(&store, [id], cli.json)?;
+            save(&path, saved, store)?;
+        }
+        Command::Lists { action: None } => print_lists(&store, cli.json)?,
+        Command::Lists {
+            action: Some(action),
+        } => {
+            match action {
+                ListAction::Add { name } => {
+                    if name.trim().is_empty() {
+                        return Err("the name cannot be empty".into());
+                    }
+                    store.add_list(name);
+                }
+                ListAction::Rename { list, name } => {
+                    if name.trim().is_empty() {
+                        return Err("the name cannot be empty".into());
+                    }
+                    let list = resolve_list(&store, &list)?;
+                    store.rename_list(list, name);
+                }
+                ListAction::Archive { list, restore } => {
+                    let list = resolve_list(&store, &list)?;
+                    if !store.archive_list(list, !restore) {
+                        return Err("the inbox cannot be archived".into());
+                    }
+                }
+                ListAction::Rm { list } => {
+                    let list = resolve_list(&store, &list)?;
+                    let (list, tasks) = store
+                        .delete_list(list)
+                        .ok_or("the inbox cannot be deleted")?;
+                    if !cli.json {
+                        println!("Deleted {} and its {} tasks", list.name, tasks.len());
+                    }
+                }
+            }
+            print_lists(&store, cli.json)?;
+            save(&path, saved, store)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Loads the todo file at `path`, treating a missing file as an empty list.
+fn load(path: &Path) -> Result<SavedState, Box<dyn std::error::Error>> {
+    if path.exists() {
+        SavedState::load(path).map_err(|error| format!("{}: {}", path.display(), error).into())
+    } else {
+        Ok(SavedState::default())
+    }
+}
+
+fn save(
+    path: &Path,
+    saved: SavedState,
+    store: TaskStore,
+) -> Result<(), Box<dyn std::error::Error>> {
+    SavedState {
+        lists: store.lists().to_vec(),
+        tasks: store.into(),
+        ..saved
+    }
+    .save(path)?;
+
+    Ok(())
+}
+
+/// Finds the single task whose id starts with `prefix`.
+fn resolve(store: &TaskStore, prefix: &str) -> Result<TaskId, Box<dyn std::error::Error>> {
+    if let Ok(id) = prefix.parse::<TaskId>() {
+        if store.get(id).is_some() {
+            return Ok(id);
+        }
+    }
+
+    let prefix = prefix.to_ascii_lowercase();
+    let mut matches = store
+        .tasks()
+        .iter()
+        .filter(|task| !prefix.is_empty() && task.id.to_string().starts_with(&prefix));
+
+    match (matches.next(), matches.next()) {
+        (Some(task), None) => Ok(task.id),
+        (Some(_), Some(_)) => Err(format!("task id `{}` is ambiguous", prefix).into()),
+        (None, _) => Err(format!("there is no task with id `{}`", prefix).into()),
+    }
+}
+
+/// Finds a list by its name, ignoring case, or by a unique id prefix.
+fn resolve_list(store: &TaskStore, name: &str) -> Result<ListId, Box<dyn std::error::Error>> {
+    let by_name = store
+        .lists()
+        .iter()
+        .find(|list| list.name.to_lowercase() == name.to_lowercase());
+
+    if let Some(list) = by_name {
+        return Ok(list.id);
+    }
+
+    let prefix = name.to_ascii_lowercase();
+    let mut matches = store
+        .lists()
+        .iter()
+        .filter(|list| !prefix.is_empty() && list.id.to_string().starts_with(&prefix));
+
+    match (matches.next(), matches.next()) {
+        (Some(list), None) => Ok(list.id),
+        (Some(_), Some(_)) => Err(format!("list id `{}` is ambiguous", prefix).into()),
+        (None, _) => Err(format!("there is no list named `{}`", name).into()),
+    }
+}
+
+/// Attaches a link as is and `file` by its absolute path, so it still opens
+/// from elsewhere.
+fn attachment(file: &Path) -> Result<Attachment, String> {
+    if let Some(link) = file.to_str().and_then(Attachment::link) {
+        return Ok(link);
+    }
+
+    std::fs::canonicalize(file)
+        .map(Attachment::new)
+        .map_err(|error| format!("{}: {}", file.display(), error))
+}
+
+fn due_date(input: &str) -> Result<NaiveDate, String> {
+    parse_due(input).ok_or_else(|| format!("`{}` is not a date like 2021/12/24", input))
+}
+
+fn tag(input: &str) -> Result<String, String> {
+    let input = input.trim();
+
+    parse_tag(input)
+        .or_else(|| parse_tag(&format!("#{}", input)))
+        .ok_or_else(|| format!("`{}` is not a tag like client-a", input))
+}
+
+fn short_id(id: TaskId) -> String {
+    id.to_string().chars().take(SHORT_ID).collect()
+}
+
+fn print_tasks(
+    store: &TaskStore,
+    ids: impl IntoIterator<Item = TaskId>,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let rows = ids
+        .into_iter()
+        .filter_map(|id| store.get(id))
+        .collect::<Vec<_>>();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+        return Ok(());
+    }
+
+    let header = [
+        "ID",
+        "",
+        "IMPORTANCE",
+        "DESCRIPTION",
+        "LIST",
+        "TAGS",
+        "FILES",
+        "DUE",
+        "ADDED",
+        "COMPLETED",
+    ];
+    let today = chrono::Local::today().naive_local();
+    let cells = rows
+        .iter()
+        .map(|task| {
+            [
+                short_id(task.id),
+                String::from(if task.completed { "[x]" } else { "[ ]" }),
+                String::from(task.importance),
+                match task.progress() {
+                    Some((done, total)) => format!("{} ({}/{})", task.description, done, total),
+                    None => task.description.clone(),
+                },
+                store
+                    .list(task.list)
+                    .map(|list| list.name.clone())
+                    .unwrap_or_default(),
+                task.tags
+                    .iter()
+                    .map(|tag| format!("#{}", tag))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                task.attachments
+                    .iter()
+                    .map(|attachment| match attachment.path() {
+                        Some(path) if !path.exists() => format!("{} (missing)", attachment.name),
+                        _ => attachment.name.clone(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                match task.due {
+                    Some(due) if task.is_overdue(today) => format!("{} !", due.format(DUE_FORMAT)),
+                    Some(due) => due.format(DUE_FORMAT).to_string(),
+                    None => String::new(),
+                },
+                task.created_at.format(DATE_FORMAT).to_string(),
+                task.completed_at
+                    .map(|date| date.format(DATE_FORMAT).to_string())
+                    .unwrap_or_default(),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    print_table(header, &cells);
+
+    Ok(())
+}
+
+/// Prints every list with its number of tasks left and in total, followed by
+/// the total over all lists.
+fn print_lists(store: &TaskStore, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let no_tags = TagFilter::default();
+    let count = |list| store.query(list, Filter::All, "", &[], &no_tags).count();
+    let left = |list| store.tasks_left(list, "", &[], &no_tags);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(store.lists())?);
+        return Ok(());
+    }
+
+    let header = ["ID", "NAME", "LEFT", "TASKS", ""];
+    let mut cells = store
+        .lists()
+        .iter()
+        .map(|list| {
+            [
+                list.id.to_string().chars().take(SHORT_ID).collect(),
+                list.name.clone(),
+                left(Some(list.id)).to_string(),
+                count(Some(list.id)).to_string(),
+                String::from(if list.archived { "archived" } else { "" }),
+            ]
+        })
+        .collect::<Vec<_>>();
+    cells.push([
+        String::new(),
+        String::from("(all lists)"),
+        left(None).to_string(),
+        count(None).to_string(),
+        String::new(),
+    ]);
+
+    print_table(header, &cells);
+
+    Ok(())
+}
+
+fn print_table<const N: usize>(header: [&str; N], cells: &[[String; N]]) {
+    let mut widths = header.map(|title| title.chars().count());
+    for row in cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let print_row = |row: &[String]| {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", line.trim_end());
+    };
+
+    print_row(&header.map(String::from));
+    for row in cells {
+        print_row(row);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tells_the_todo_file_from_the_files_to_attach() {
+        let cli = Cli::try_parse_from([
+            "ex_todo",
+            "--todo-file",
+            "t.json",
+            "add",
+            "x",
+            "--file",
+            "r.txt",
+            "-f",
+            "s.txt",
+        ])
+        .unwrap();
+
+        assert_eq!(cli.todo_file, Some(PathBuf::from("t.json")));
+        match cli.command {
+            Command::Add { file, .. } => {
+                assert_eq!(file, [PathBuf::from("r.txt"), PathBuf::from("s.txt")])
+            }
+            command => panic!("parsed as {:?}", command),
+        }
+    }
+
+    #[test]
+    fn takes_the_todo_file_after_the_subcommand() {
+        let cli = Cli::try_parse_from(["ex_todo", "add", "x", "--todo-file", "t.json"]).unwrap();
+
+        assert_eq!(cli.todo_file, Some(PathBuf::from("t.json")));
+        match cli.command {
+            Command::Add { file, .. } => assert!(file.is_empty()),
+            command => panic!("parsed as {:?}", command),
+        }
+    }
+}
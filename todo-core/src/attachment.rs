@@ -0,0 +1,239 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// Schemes recognised as links when typed into a task.
+const LINK_SCHEMES: [&str; 4] = ["http", "https", "mailto", "file"];
+
+/// Punctuation that ends the sentence around a link rather than the link.
+const TRAILING_PUNCTUATION: [char; 7] = [',', '.', ';', ':', '!', '?', ')'];
+
+/// A file or link kept with a task.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attachment {
+    #[serde(flatten)]
+    pub target: Target,
+    /// Shown instead of the full path or link; the file name unless renamed.
+    pub name: String,
+    pub added_at: DateTime<Local>,
+}
+
+/// What an attachment points at.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum Target {
+    LocalFile { path: PathBuf },
+    Url { url: String },
+}
+
+impl Attachment {
+    pub fn new(path: PathBuf) -> Self {
+        Attachment {
+            name: display_name(&path),
+            target: Target::LocalFile { path },
+            added_at: Local::now(),
+        }
+    }
+
+    /// Reads an http(s), mailto or file:// link; `file://` links become
+    /// local files. Punctuation right after the link is left out.
+    pub fn link(text: &str) -> Option<Self> {
+        let (link, _) = split_punctuation(text);
+        let url = Url::parse(link).ok()?;
+
+        if !LINK_SCHEMES.contains(&url.scheme()) {
+            return None;
+        }
+
+        if url.scheme() == "file" {
+            return url.to_file_path().ok().map(Attachment::new);
+        }
+
+        let name = match url.scheme() {
+            "mailto" => url.path().to_string(),
+            _ => format!(
+                "{}{}",
+                url.host_str().unwrap_or_default(),
+                url.path().trim_end_matches('/')
+            ),
+        };
+
+        Some(Attachment {
+            name,
+            target: Target::Url { url: url.into() },
+            added_at: Local::now(),
+        })
+    }
+
+    /// The local file, unless this is a link.
+    pub fn path(&self) -> Option<&Path> {
+        match &self.target {
+            Target::LocalFile { path } => Some(path),
+            Target::Url { .. } => None,
+        }
+    }
+}
+
+impl Target {
+    /// What to hand to the system launcher to open the attachment.
+    pub fn location(&self) -> &OsStr {
+        match self {
+            Target::LocalFile { path } => path.as_os_str(),
+            Target::Url { url } => OsStr::new(url),
+        }
+    }
+}
+
+/// Splits the links out of a typed description, returning the remaining
+/// text and the links as attachments.
+pub fn extract_links(text: &str) -> (String, Vec<Attachment>) {
+    let mut words: Vec<String> = Vec::new();
+    let mut links = Vec::new();
+
+    for word in text.split_whitespace() {
+        match Attachment::link(word) {
+            Some(link) => {
+                links.push(link);
+
+                // Stays with the sentence: "see <link>, then" is "see, then".
+                let (_, punctuation) = split_punctuation(word);
+
+                if let Some(last) = words.last_mut() {
+                    last.push_str(punctuation);
+                }
+            }
+            None => words.push(word.to_string()),
+        }
+    }
+
+    if links.is_empty() {
+        (text.to_string(), links)
+    } else {
+        (words.join(" "), links)
+    }
+}
+
+/// Splits `word` into the link and the punctuation after it. A closing
+/// parenthesis is kept when the link opened one, as in Wikipedia links.
+fn split_punctuation(word: &str) -> (&str, &str) {
+    let mut link = word;
+
+    while let Some(rest) = link.strip_suffix(TRAILING_PUNCTUATION) {
+        if link.ends_with(')') && rest.matches('(').count() > rest.matches(')').count() {
+            break;
+        }
+
+        link = rest;
+    }
+
+    word.split_at(link.len())
+}
+
+/// The file name of `path`, or the whole path when it has none (e.g. `/`).
+pub(crate) fn display_name(path: &Path) -> String {
+    match path.file_name() {
+        Some(name) => name.to_string_lossy().into_owned(),
+        None => path.to_string_lossy().into_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(attachment: &Attachment) -> &str {
+        match &attachment.target {
+            Target::Url { url } => url,
+            Target::LocalFile { path } => panic!("a local file: {}", path.display()),
+        }
+    }
+
+    #[test]
+    fn reads_a_link_and_names_it_after_its_host_and_path() {
+        let link = Attachment::link("https://example.com/docs/").unwrap();
+
+        assert_eq!(url(&link), "https://example.com/docs/");
+        assert_eq!(link.name, "example.com/docs");
+    }
+
+    #[test]
+    fn reads_a_mailto_link_as_the_address() {
+        let link = Attachment::link("mailto:someone@example.com").unwrap();
+
+        assert_eq!(link.name, "someone@example.com");
+    }
+
+    #[test]
+    fn turns_a_file_link_into_a_local_file() {
+        let link = Attachment::link("file:///tmp/report.pdf").unwrap();
+
+        assert_eq!(link.path(), Some(Path::new("/tmp/report.pdf")));
+        assert_eq!(link.name, "report.pdf");
+    }
+
+    #[test]
+    fn ignores_other_schemes_and_plain_words() {
+        for text in [
+            "ftp://example.com/x",
+            "javascript:alert(1)",
+            "report",
+            "a:b",
+        ] {
+            assert!(Attachment::link(text).is_none(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn leaves_trailing_punctuation_out_of_a_link() {
+        for text in [
+            "https://example.com/x,",
+            "https://example.com/x.",
+            "https://example.com/x;",
+            "https://example.com/x:",
+            "https://example.com/x!",
+            "https://example.com/x?",
+            "https://example.com/x)",
+            "https://example.com/x).",
+        ] {
+            let link = Attachment::link(text).unwrap();
+
+            assert_eq!(url(&link), "https://example.com/x", "{}", text);
+            assert_eq!(link.name, "example.com/x", "{}", text);
+        }
+    }
+
+    #[test]
+    fn keeps_a_closing_parenthesis_the_link_opened() {
+        let link = Attachment::link("https://en.wikipedia.org/wiki/Rust_(language).").unwrap();
+
+        assert_eq!(url(&link), "https://en.wikipedia.org/wiki/Rust_(language)");
+    }
+
+    #[test]
+    fn extracts_links_and_keeps_the_punctuation_in_the_text() {
+        let (text, links) = extract_links("see https://example.com/x, then mail mailto:a@b.c.");
+
+        assert_eq!(text, "see, then mail.");
+        assert_eq!(links.len(), 2);
+        assert_eq!(url(&links[0]), "https://example.com/x");
+        assert_eq!(url(&links[1]), "mailto:a@b.c");
+    }
+
+    #[test]
+    fn drops_the_punctuation_of_a_link_that_starts_the_text() {
+        let (text, links) = extract_links("https://example.com/x.");
+
+        assert_eq!(text, "");
+        assert_eq!(links.len(), 1);
+    }
+
+    #[test]
+    fn leaves_text_without_links_as_typed() {
+        let (text, links) = extract_links("  call  back ");
+
+        assert_eq!(text, "  call  back ");
+        assert!(links.is_empty());
+    }
+}
@@ -0,0 +1,164 @@
+//! Rolling copies of the todo file: `todos.json.1` is the newest and
+//! `todos.json.<backup_count>` the oldest one kept. Next to them are the
+//! timestamped copies made when the file could not be read or changes could
+//! not be written to it.
+
+use crate::SavedState;
+use chrono::{DateTime, Local};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Autosave runs every couple of seconds; rotating on each of those would
+/// leave a set of near identical backups, so the newest one is only
+/// replaced once it is at least this old.
+const ROTATE_AFTER: Duration = Duration::from_secs(10 * 60);
+
+/// A backup found next to the todo file.
+#[derive(Debug, Clone)]
+pub struct Backup {
+    pub path: PathBuf,
+    pub kind: BackupKind,
+    pub modified: DateTime<Local>,
+    /// `None` when the backup itself cannot be read.
+    pub tasks: Option<usize>,
+}
+
+/// Why a backup was made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackupKind {
+    /// `todos.json.<n>`, made before the file is replaced.
+    Rolling(usize),
+    /// `todos.json.unsaved-<timestamp>`, changes that could not go into the
+    /// file.
+    Unsaved,
+    /// `todos.json.unreadable-<timestamp>`, the file as it was when it could
+    /// not be read.
+    Unreadable,
+}
+
+impl BackupKind {
+    fn of(suffix: &str) -> Option<BackupKind> {
+        if suffix.starts_with("unsaved-") {
+            Some(BackupKind::Unsaved)
+        } else if suffix.starts_with("unreadable-") {
+            Some(BackupKind::Unreadable)
+        } else {
+            suffix.parse().ok().map(BackupKind::Rolling)
+        }
+    }
+}
+
+fn numbered(path: &Path, n: usize) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(format!(".{}", n));
+    name.into()
+}
+
+/// Shifts the existing backups of `path` up by one and copies `path` into
+/// the freed `.1` slot, dropping whatever falls past `backup_count`.
+pub(crate) fn rotate(path: &Path, backup_count: usize) -> std::io::Result<()> {
+    if backup_count == 0 || !path.exists() {
+        return Ok(());
+    }
+
+    let newest = numbered(path, 1);
+    let recent = std::fs::metadata(&newest)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age < ROTATE_AFTER);
+
+    if recent {
+        return Ok(());
+    }
+
+    let _ = std::fs::remove_file(numbered(path, backup_count));
+
+    for n in (1..backup_count).rev() {
+        let from = numbered(path, n);
+
+        if from.exists() {
+            std::fs::rename(&from, numbered(path, n + 1))?;
+        }
+    }
+
+    std::fs::copy(path, &newest)?;
+
+    Ok(())
+}
+
+/// The backups of `path`: the copies put aside, newest first, then the
+/// rolling ones, newest first.
+pub(crate) fn list(path: &Path) -> Vec<Backup> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}.", name.to_string_lossy());
+
+    let mut backups = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let kind = BackupKind::of(file_name.strip_prefix(&prefix)?)?;
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            let path = entry.path();
+            let tasks = SavedState::load_from(&path)
+                .ok()
+                .map(|state| state.tasks.len());
+
+            Some(Backup {
+                path,
+                kind,
+                modified: modified.into(),
+                tasks,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    backups.sort_by_key(|backup| match backup.kind {
+        BackupKind::Rolling(n) => (1, n, None),
+        _ => (0, 0, Some(std::cmp::Reverse(backup.modified))),
+    });
+
+    backups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_the_copies_put_aside_before_the_rolling_ones() {
+        let dir = std::env::temp_dir().join(format!("ex_todo-backup-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("todos.json");
+        let tasks = serde_json::to_string(&SavedState::default()).unwrap();
+
+        for name in [
+            "todos.json",
+            "todos.json.2",
+            "todos.json.1",
+            "todos.json.unsaved-20211224-103000",
+            "todos.json.unreadable-20211224-100000",
+            "todos.json.lock",
+            "other.json.1",
+        ] {
+            std::fs::write(dir.join(name), &tasks).unwrap();
+        }
+
+        let kinds = list(&path)
+            .into_iter()
+            .map(|backup| backup.kind)
+            .collect::<Vec<_>>();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(kinds.len(), 4);
+        assert!(kinds[..2].contains(&BackupKind::Unsaved));
+        assert!(kinds[..2].contains(&BackupKind::Unreadable));
+        assert_eq!(kinds[2..], [BackupKind::Rolling(1), BackupKind::Rolling(2)]);
+    }
+}
@@ -0,0 +1,91 @@
+//! Where the app keeps its data, config and cache.
+//!
+//! Earlier builds shared the directories of the upstream iced todos example,
+//! whose `todos.json` has another layout. The first launch with the app's own
+//! directories copies what it finds there across, and leaves the originals
+//! to the example.
+
+use directories_next::ProjectDirs;
+use std::io;
+use std::path::Path;
+use std::sync::Once;
+
+static ADOPT: Once = Once::new();
+
+/// The app's directories, filled from the shared ones the first time.
+pub fn project_dirs() -> Option<ProjectDirs> {
+    let dirs = ProjectDirs::from("rs", "", "ex_todo")?;
+
+    ADOPT.call_once(|| {
+        if let Some(old) = ProjectDirs::from("rs", "Iced", "Todos") {
+            adopt(old.data_dir(), dirs.data_dir());
+            adopt(old.config_dir(), dirs.config_dir());
+        }
+    });
+
+    Some(dirs)
+}
+
+/// Copies `old` to `new` unless `new` already exists.
+fn adopt(old: &Path, new: &Path) {
+    if new.exists() || !old.is_dir() {
+        return;
+    }
+
+    if let Err(error) = copy_dir(old, new) {
+        eprintln!(
+            "Cannot copy {} to {}: {}",
+            old.display(),
+            new.display(),
+            error
+        );
+    }
+}
+
+/// Copies the directory `from` into `to`, leaving out the locks of the
+/// instances running on it.
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    std::fs::create_dir_all(to)?;
+
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            copy_dir(&path, &to.join(entry.file_name()))?;
+        } else if path.extension() != Some("lock".as_ref()) {
+            std::fs::copy(&path, to.join(entry.file_name()))?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copies_a_directory_without_its_locks() {
+        let root = std::env::temp_dir().join(format!("ex_todo-dirs-{}", std::process::id()));
+        let old = root.join("old");
+        let new = root.join("new");
+        std::fs::create_dir_all(old.join("theme")).unwrap();
+        std::fs::write(old.join("todos.json"), "{}").unwrap();
+        std::fs::write(old.join("todos.json.lock"), "{}").unwrap();
+        std::fs::write(old.join("theme").join("file.png"), "").unwrap();
+
+        adopt(&old, &new);
+
+        assert!(new.join("todos.json").is_file());
+        assert!(new.join("theme").join("file.png").is_file());
+        assert!(!new.join("todos.json.lock").exists());
+
+        // Only the first time: the new directory is left alone after that.
+        std::fs::remove_file(new.join("todos.json")).unwrap();
+        adopt(&old, &new);
+        assert!(!new.join("todos.json").exists());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
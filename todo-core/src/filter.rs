@@ -0,0 +1,137 @@
+use crate::{Importance, Task};
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Filter {
+    #[default]
+    All,
+    Active,
+    Completed,
+    Overdue,
+    Today,
+    ThisWeek,
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "all" => Ok(Filter::All),
+            "active" => Ok(Filter::Active),
+            "completed" => Ok(Filter::Completed),
+            "overdue" => Ok(Filter::Overdue),
+            "today" => Ok(Filter::Today),
+            "this-week" | "week" => Ok(Filter::ThisWeek),
+            _ => Err(format!(
+                "unknown filter `{}` (expected all, active, completed, overdue, today or this-week)",
+                s
+            )),
+        }
+    }
+}
+
+impl Filter {
+    pub fn matches(&self, task: &Task) -> bool {
+        self.matches_on(task, Local::today().naive_local())
+    }
+
+    /// Same as [`Filter::matches`], with the due date filters relative to `today`.
+    pub fn matches_on(&self, task: &Task, today: NaiveDate) -> bool {
+        match self {
+            Filter::All => true,
+            Filter::Active => !task.completed,
+            Filter::Completed => task.completed,
+            Filter::Overdue => task.is_overdue(today),
+            Filter::Today => task.due == Some(today),
+            Filter::ThisWeek => {
+                let monday = today - Duration::days(today.weekday().num_days_from_monday().into());
+                let sunday = monday + Duration::days(6);
+
+                task.due.is_some_and(|due| monday <= due && due <= sunday)
+            }
+        }
+    }
+
+    pub fn word_matches(&self, task: &Task, filter_input_value: &str) -> bool {
+        task.description.contains(filter_input_value)
+            || task
+                .subtasks
+                .iter()
+                .any(|subtask| subtask.description.contains(filter_input_value))
+    }
+
+    /// Whether the task has one of the selected `importances`; selecting none
+    /// shows every task.
+    pub fn importance_matches(&self, task: &Task, importances: &[Importance]) -> bool {
+        importances.is_empty() || importances.contains(&task.importance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A Wednesday.
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd(2021, 12, 22)
+    }
+
+    fn due(days: i64) -> Task {
+        Task::new(
+            String::from("task"),
+            Vec::new(),
+            Importance::Normal,
+            Some(today() + Duration::days(days)),
+        )
+    }
+
+    fn undated() -> Task {
+        Task::new(String::from("task"), Vec::new(), Importance::Normal, None)
+    }
+
+    #[test]
+    fn tells_open_tasks_from_completed_ones() {
+        let open = undated();
+        let mut done = undated();
+        done.set_completed(true);
+
+        assert!(Filter::Active.matches_on(&open, today()));
+        assert!(!Filter::Active.matches_on(&done, today()));
+        assert!(Filter::Completed.matches_on(&done, today()));
+        assert!(!Filter::Completed.matches_on(&open, today()));
+        assert!(Filter::All.matches_on(&done, today()));
+    }
+
+    #[test]
+    fn finds_open_tasks_past_their_due_date() {
+        let mut done = due(-1);
+        done.set_completed(true);
+
+        assert!(Filter::Overdue.matches_on(&due(-1), today()));
+        assert!(!Filter::Overdue.matches_on(&due(0), today()));
+        assert!(!Filter::Overdue.matches_on(&done, today()));
+        assert!(!Filter::Overdue.matches_on(&undated(), today()));
+    }
+
+    #[test]
+    fn finds_tasks_due_today() {
+        assert!(Filter::Today.matches_on(&due(0), today()));
+        assert!(!Filter::Today.matches_on(&due(1), today()));
+        assert!(!Filter::Today.matches_on(&undated(), today()));
+    }
+
+    #[test]
+    fn finds_tasks_due_from_monday_to_sunday() {
+        // Monday 20th to Sunday 26th.
+        for days in -2..=4 {
+            assert!(Filter::ThisWeek.matches_on(&due(days), today()), "{}", days);
+        }
+
+        assert!(!Filter::ThisWeek.matches_on(&due(-3), today()));
+        assert!(!Filter::ThisWeek.matches_on(&due(5), today()));
+        assert!(!Filter::ThisWeek.matches_on(&undated(), today()));
+    }
+}
@@ -0,0 +1,30 @@
+//! Headless task model and persistence shared by every todo front-end.
+//!
+//! Nothing in here knows about iced: widget state lives in the GUI crate and
+//! is kept alongside the tasks owned by a [`TaskStore`].
+
+mod attachment;
+mod backup;
+mod dirs;
+mod filter;
+mod list;
+mod merge;
+mod migration;
+mod persistence;
+mod sort;
+mod store;
+mod tag;
+mod task;
+
+pub use attachment::{extract_links, Attachment, Target};
+pub use backup::{Backup, BackupKind};
+pub use dirs::project_dirs;
+pub use filter::Filter;
+pub use list::{ListId, TaskList};
+pub use merge::{Conflict, Merge};
+pub use migration::CURRENT_VERSION;
+pub use persistence::{LoadError, SaveError, SavedState, PROJECT_FILE};
+pub use sort::Sort;
+pub use store::TaskStore;
+pub use tag::{extract_tags, parse_tag, TagFilter, TagMode};
+pub use task::{parse_description, parse_due, Importance, Subtask, Task, TaskId, TypedDescription};
@@ -0,0 +1,75 @@
+use crate::Filter;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Persisted identifier of a task list.
+///
+/// Tasks saved before there were several lists belong to the inbox, whose id
+/// is the nil UUID so it needs no migration.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ListId(Uuid);
+
+impl ListId {
+    pub const INBOX: ListId = ListId(Uuid::nil());
+
+    pub fn new() -> Self {
+        ListId(Uuid::new_v4())
+    }
+}
+
+impl Default for ListId {
+    fn default() -> Self {
+        ListId::INBOX
+    }
+}
+
+impl fmt::Display for ListId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for ListId {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Uuid::parse_str(s).map(ListId)
+    }
+}
+
+/// A named list of tasks, such as "Inbox" or "Client A".
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskList {
+    pub id: ListId,
+    pub name: String,
+    /// Hidden from the switcher, its tasks kept.
+    #[serde(default)]
+    pub archived: bool,
+    /// The filter last chosen while the list was shown.
+    #[serde(default)]
+    pub filter: Filter,
+}
+
+impl TaskList {
+    pub fn new(name: String) -> Self {
+        TaskList {
+            id: ListId::new(),
+            name,
+            archived: false,
+            filter: Filter::default(),
+        }
+    }
+
+    /// The list every task starts in; it can be renamed but not deleted.
+    pub fn inbox() -> Self {
+        TaskList {
+            id: ListId::INBOX,
+            name: String::from("Inbox"),
+            archived: false,
+            filter: Filter::default(),
+        }
+    }
+}
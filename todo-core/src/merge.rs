@@ -0,0 +1,261 @@
+//! Three-way merge of the tasks changed both in the app and, meanwhile, in
+//! the file on disk, task by task.
+
+use crate::{Task, TaskId, TaskList};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// The tasks and lists both sides agree on, and the tasks they don't.
+#[derive(Debug, Clone)]
+pub struct Merge {
+    pub lists: Vec<TaskList>,
+    /// Conflicting tasks hold the local version until resolved.
+    pub tasks: Vec<Task>,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// A task changed differently on each side; `None` where it was deleted.
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub id: TaskId,
+    pub local: Option<Task>,
+    pub remote: Option<Task>,
+}
+
+impl Merge {
+    /// Merges the `local` and `remote` versions of the tasks and lists last
+    /// saved as `base`. Lists changed on both sides keep the local version.
+    pub fn new(
+        base: (&[TaskList], &[Task]),
+        local: (&[TaskList], &[Task]),
+        remote: (&[TaskList], &[Task]),
+    ) -> Merge {
+        let (lists, _) = merge_by(base.0, local.0, remote.0, |list| list.id);
+        let (tasks, conflicts) = merge_by(base.1, local.1, remote.1, |task| task.id);
+
+        Merge {
+            lists,
+            tasks,
+            conflicts: conflicts
+                .into_iter()
+                .map(|(id, local, remote)| Conflict { id, local, remote })
+                .collect(),
+        }
+    }
+
+    /// The merged tasks with each conflict settled by `take_remote`, which
+    /// tells for every conflict, in order, whether the file's version wins.
+    pub fn resolve(&self, take_remote: &[bool]) -> Vec<Task> {
+        let mut tasks = self.tasks.clone();
+
+        for (conflict, &take_remote) in self.conflicts.iter().zip(take_remote) {
+            if !take_remote {
+                continue;
+            }
+
+            let position = tasks.iter().position(|task| task.id == conflict.id);
+
+            match (position, &conflict.remote) {
+                (Some(position), Some(remote)) => tasks[position] = remote.clone(),
+                (Some(position), None) => {
+                    tasks.remove(position);
+                }
+                (None, Some(remote)) => tasks.push(remote.clone()),
+                (None, None) => {}
+            }
+        }
+
+        tasks
+    }
+}
+
+/// An item changed differently on each side, as `(key, local, remote)`.
+type Disagreement<K, T> = (K, Option<T>, Option<T>);
+
+/// Items kept in the local order, followed by those only added remotely,
+/// and the items whose sides disagree.
+fn merge_by<T: Clone + PartialEq, K: Copy + Eq + Hash>(
+    base: &[T],
+    local: &[T],
+    remote: &[T],
+    key: impl Fn(&T) -> K,
+) -> (Vec<T>, Vec<Disagreement<K, T>>) {
+    let index = |items: &[T]| {
+        items
+            .iter()
+            .map(|item| (key(item), item.clone()))
+            .collect::<HashMap<_, _>>()
+    };
+    let (base_items, local_items, remote_items) = (index(base), index(local), index(remote));
+
+    let mut keys = local.iter().map(&key).collect::<Vec<_>>();
+    keys.extend(
+        remote
+            .iter()
+            .map(&key)
+            .filter(|key| !local_items.contains_key(key)),
+    );
+
+    let mut merged = Vec::new();
+    let mut conflicts = Vec::new();
+
+    for key in keys {
+        let base = base_items.get(&key);
+        let local = local_items.get(&key);
+        let remote = remote_items.get(&key);
+
+        let kept = if local == remote || remote == base {
+            local
+        } else if local == base {
+            remote
+        } else {
+            conflicts.push((key, local.cloned(), remote.cloned()));
+            local
+        };
+
+        merged.extend(kept.cloned());
+    }
+
+    (merged, conflicts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Importance;
+
+    fn task(description: &str) -> Task {
+        Task::new(
+            description.to_string(),
+            Vec::new(),
+            Importance::Normal,
+            None,
+        )
+    }
+
+    fn edited(task: &Task, description: &str) -> Task {
+        Task {
+            description: description.to_string(),
+            ..task.clone()
+        }
+    }
+
+    fn merge(base: Vec<Task>, local: Vec<Task>, remote: Vec<Task>) -> Merge {
+        let lists = [TaskList::inbox()];
+
+        Merge::new((&lists, &base), (&lists, &local), (&lists, &remote))
+    }
+
+    #[test]
+    fn keeps_the_side_that_changed() {
+        let (a, b) = (task("a"), task("b"));
+        let local_a = edited(&a, "a, locally");
+        let remote_b = edited(&b, "b, remotely");
+
+        let merge = merge(
+            vec![a.clone(), b.clone()],
+            vec![local_a.clone(), b],
+            vec![a, remote_b.clone()],
+        );
+
+        assert!(merge.conflicts.is_empty());
+        assert_eq!(merge.tasks, vec![local_a, remote_b]);
+    }
+
+    #[test]
+    fn an_edit_on_both_sides_conflicts() {
+        let a = task("a");
+        let local = edited(&a, "a, locally");
+        let remote = edited(&a, "a, remotely");
+
+        let merge = merge(vec![a.clone()], vec![local.clone()], vec![remote.clone()]);
+
+        assert_eq!(merge.tasks, vec![local.clone()]);
+        assert_eq!(merge.conflicts.len(), 1);
+        assert_eq!(merge.conflicts[0].id, a.id);
+        assert_eq!(merge.conflicts[0].local, Some(local));
+        assert_eq!(merge.conflicts[0].remote, Some(remote));
+    }
+
+    #[test]
+    fn the_same_edit_on_both_sides_agrees() {
+        let a = task("a");
+        let both = edited(&a, "a, on both sides");
+
+        let merge = merge(vec![a], vec![both.clone()], vec![both.clone()]);
+
+        assert!(merge.conflicts.is_empty());
+        assert_eq!(merge.tasks, vec![both]);
+    }
+
+    #[test]
+    fn a_local_delete_against_a_remote_edit_conflicts() {
+        let a = task("a");
+        let remote = edited(&a, "a, remotely");
+
+        let merge = merge(vec![a.clone()], vec![], vec![remote.clone()]);
+
+        // Deleted until the remote edit is chosen.
+        assert!(merge.tasks.is_empty());
+        assert_eq!(merge.conflicts.len(), 1);
+        assert_eq!(merge.conflicts[0].local, None);
+        assert_eq!(merge.conflicts[0].remote, Some(remote.clone()));
+        assert_eq!(merge.resolve(&[true]), vec![remote]);
+        assert!(merge.resolve(&[false]).is_empty());
+    }
+
+    #[test]
+    fn a_remote_delete_of_an_untouched_task_applies() {
+        let (a, b) = (task("a"), task("b"));
+
+        let merge = merge(
+            vec![a.clone(), b.clone()],
+            vec![a.clone(), b.clone()],
+            vec![a.clone()],
+        );
+
+        assert!(merge.conflicts.is_empty());
+        assert_eq!(merge.tasks, vec![a]);
+    }
+
+    #[test]
+    fn adds_come_in_from_both_sides() {
+        let a = task("a");
+        let (local, remote) = (task("local"), task("remote"));
+
+        let merge = merge(
+            vec![a.clone()],
+            vec![a.clone(), local.clone()],
+            vec![remote.clone(), a.clone()],
+        );
+
+        assert!(merge.conflicts.is_empty());
+        // The local order, then what only the file has.
+        assert_eq!(merge.tasks, vec![a, local, remote]);
+    }
+
+    #[test]
+    fn resolves_each_conflict_as_chosen() {
+        let (a, b, c) = (task("a"), task("b"), task("c"));
+        let (local_a, remote_a) = (edited(&a, "a, locally"), edited(&a, "a, remotely"));
+        let (local_b, remote_b) = (edited(&b, "b, locally"), edited(&b, "b, remotely"));
+        let local_c = edited(&c, "c, locally");
+
+        let merge = merge(
+            vec![a, b, c],
+            vec![local_a, local_b.clone(), local_c.clone()],
+            // `c` deleted in the file.
+            vec![remote_a.clone(), remote_b],
+        );
+
+        assert_eq!(merge.conflicts.len(), 3);
+        assert_eq!(
+            merge.resolve(&[true, false, true]),
+            vec![remote_a.clone(), local_b.clone()]
+        );
+        assert_eq!(
+            merge.resolve(&[true, false, false]),
+            vec![remote_a, local_b, local_c]
+        );
+    }
+}
@@ -0,0 +1,295 @@
+//! Upgrades of older `todos.json` layouts, applied to the raw JSON before it
+//! is deserialized.
+//!
+//! Every layout change bumps [`CURRENT_VERSION`] and appends one step to
+//! [`MIGRATIONS`]; a file is upgraded by running the steps from its own
+//! `version` onwards. Files written before the field existed are version 0.
+
+use crate::attachment::display_name;
+use crate::{LoadError, TaskId};
+use chrono::{Local, NaiveDateTime, TimeZone};
+use serde_json::{json, Map, Value};
+use std::path::Path;
+
+/// Version written by this build.
+pub const CURRENT_VERSION: u32 = 5;
+
+/// `MIGRATIONS[n]` upgrades a version `n` file to version `n + 1`.
+const MIGRATIONS: [fn(&mut Map<String, Value>); CURRENT_VERSION as usize] = [
+    add_task_ids,
+    timestamps_from_date,
+    attachments_from_file_path,
+    attachment_kinds,
+    subtasks_tags_and_lists,
+];
+
+/// Format of the preformatted `date` string written by version 0.
+const LEGACY_DATE_FORMAT: &str = "Added %Y/%m/%d %H:%M";
+
+/// Brings `state` up to [`CURRENT_VERSION`].
+///
+/// Returns whether anything changed, in which case the caller should write
+/// the upgraded file back so all front-ends agree on the migrated values.
+pub(crate) fn migrate(state: &mut Value) -> Result<bool, LoadError> {
+    let version = match state.get("version") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or(LoadError::FormatError)?,
+    };
+
+    if version > CURRENT_VERSION {
+        return Err(LoadError::UnsupportedVersion(version));
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        for task in tasks(state) {
+            migration(task);
+        }
+    }
+
+    state["version"] = json!(CURRENT_VERSION);
+
+    Ok(version < CURRENT_VERSION)
+}
+
+fn tasks(state: &mut Value) -> impl Iterator<Item = &mut Map<String, Value>> {
+    state
+        .get_mut("tasks")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_object_mut)
+}
+
+/// 0 → 1: tasks were addressed by their position in the list.
+fn add_task_ids(task: &mut Map<String, Value>) {
+    if !task.contains_key("id") {
+        task.insert("id".into(), json!(TaskId::new()));
+    }
+}
+
+/// 1 → 2: a single preformatted `date` string became real timestamps.
+fn timestamps_from_date(task: &mut Map<String, Value>) {
+    if let Some(date) = task.remove("date") {
+        // Tasks typed in without a dropped file often had no date at all;
+        // the best we know is that they exist as of now.
+        let created_at = date
+            .as_str()
+            .and_then(|date| NaiveDateTime::parse_from_str(date.trim(), LEGACY_DATE_FORMAT).ok())
+            .and_then(|date| Local.from_local_datetime(&date).earliest())
+            .unwrap_or_else(Local::now);
+
+        task.insert("created_at".into(), json!(created_at));
+        task.insert("updated_at".into(), json!(created_at));
+        task.insert("completed_at".into(), Value::Null);
+    }
+}
+
+/// 2 → 3: a single `file_path`, empty when nothing was attached, became a
+/// list of attachments.
+fn attachments_from_file_path(task: &mut Map<String, Value>) {
+    if let Some(file_path) = task.remove("file_path") {
+        let attachments = match file_path.as_str() {
+            Some(path) if !path.is_empty() => json!([{
+                "path": path,
+                "name": display_name(Path::new(path)),
+                // The file came with the task.
+                "added_at": task.get("created_at").cloned().unwrap_or_else(|| json!(Local::now())),
+            }]),
+            _ => json!([]),
+        };
+
+        task.insert("attachments".into(), attachments);
+    }
+}
+
+/// 3 → 4: attachments could only be local files and had no `kind`.
+fn attachment_kinds(task: &mut Map<String, Value>) {
+    let attachments = task
+        .get_mut("attachments")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_object_mut);
+
+    for attachment in attachments {
+        attachment
+            .entry("kind")
+            .or_insert_with(|| json!("LocalFile"));
+    }
+}
+
+/// 4 → 5: subtasks, tags and lists were added. A file without them reads
+/// as tasks with none, all in the inbox, so there is nothing to convert; the
+/// bump only keeps older builds from dropping them when saving.
+fn subtasks_tags_and_lists(_task: &mut Map<String, Value>) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ListId, SavedState, Target};
+    use std::path::PathBuf;
+
+    fn load(mut value: Value) -> (bool, SavedState) {
+        let migrated = migrate(&mut value).expect("migrates");
+        let state = serde_json::from_value(value).expect("deserializes");
+
+        (migrated, state)
+    }
+
+    #[test]
+    fn upgrades_a_baseline_file() {
+        let (migrated, state) = load(json!({
+            "input_value": "",
+            "filter": "All",
+            "tasks": [
+                {
+                    "description": "Send the report",
+                    "file_path": "/home/me/docs/report.pdf",
+                    "completed": false,
+                    "date": " Added 2021/12/24 10:30",
+                    "importance": "High",
+                },
+                {
+                    "description": "Call back",
+                    "file_path": "",
+                    "completed": true,
+                    "date": "",
+                    "importance": "Normal",
+                },
+            ],
+        }));
+
+        assert!(migrated);
+        assert_eq!(state.version, CURRENT_VERSION);
+        assert_eq!(state.tasks.len(), 2);
+        assert_ne!(state.tasks[0].id, state.tasks[1].id);
+
+        let report = &state.tasks[0];
+        let added = Local.ymd(2021, 12, 24).and_hms(10, 30, 0);
+
+        assert_eq!(report.description, "Send the report");
+        assert_eq!(report.importance, crate::Importance::High);
+        assert_eq!(report.created_at, added);
+        assert_eq!(report.updated_at, added);
+        assert_eq!(report.completed_at, None);
+        assert_eq!(report.attachments.len(), 1);
+        assert_eq!(
+            report.attachments[0].target,
+            Target::LocalFile {
+                path: PathBuf::from("/home/me/docs/report.pdf")
+            }
+        );
+        assert_eq!(report.attachments[0].name, "report.pdf");
+        assert_eq!(report.attachments[0].added_at, added);
+
+        // No date to go by: created as of the migration.
+        let call_back = &state.tasks[1];
+
+        assert!(call_back.completed);
+        assert!(call_back.attachments.is_empty());
+        assert!(call_back.created_at > added);
+    }
+
+    #[test]
+    fn keeps_the_ids_of_an_unversioned_file_with_ids() {
+        let dated = TaskId::new();
+        let stamped = TaskId::new();
+        let created_at = Local.ymd(2022, 1, 5).and_hms(9, 0, 0);
+
+        let (migrated, state) = load(json!({
+            "input_value": "",
+            "filter": "Active",
+            "tasks": [
+                // Before timestamps replaced the date string.
+                {
+                    "id": dated,
+                    "description": "Dated",
+                    "file_path": "",
+                    "completed": false,
+                    "date": " Added 2022/01/05 09:00",
+                    "importance": "Low",
+                },
+                // Before due dates and the version field.
+                {
+                    "id": stamped,
+                    "description": "Stamped",
+                    "file_path": "notes.txt",
+                    "completed": false,
+                    "importance": "Normal",
+                    "created_at": created_at,
+                    "updated_at": created_at,
+                    "completed_at": null,
+                },
+            ],
+        }));
+
+        assert!(migrated);
+        assert_eq!(state.version, CURRENT_VERSION);
+        assert_eq!(state.tasks[0].id, dated);
+        assert_eq!(state.tasks[0].created_at, created_at);
+        assert_eq!(state.tasks[1].id, stamped);
+        assert_eq!(state.tasks[1].created_at, created_at);
+        assert_eq!(state.tasks[1].due, None);
+        assert_eq!(state.tasks[1].attachments[0].name, "notes.txt");
+        assert_eq!(state.tasks[1].attachments[0].added_at, created_at);
+    }
+
+    #[test]
+    fn upgrades_a_file_from_before_lists() {
+        let (migrated, state) = load(json!({
+            "version": 4,
+            "input_value": "",
+            "filter": "All",
+            "tasks": [{
+                "id": TaskId::new(),
+                "description": "Send the report",
+                "completed": false,
+                "importance": "Normal",
+                "created_at": Local::now(),
+                "updated_at": Local::now(),
+                "completed_at": null,
+                "attachments": [],
+            }],
+        }));
+
+        assert!(migrated);
+        assert_eq!(state.version, CURRENT_VERSION);
+
+        let task = &state.tasks[0];
+
+        assert!(task.subtasks.is_empty());
+        assert!(task.tags.is_empty());
+        assert_eq!(task.list, ListId::INBOX);
+    }
+
+    #[test]
+    fn leaves_a_current_file_alone() {
+        let mut value = serde_json::to_value(SavedState::default()).unwrap();
+        let before = value.clone();
+
+        assert!(!migrate(&mut value).unwrap());
+        assert_eq!(value, before);
+    }
+
+    #[test]
+    fn refuses_a_newer_version() {
+        let mut value = json!({ "version": CURRENT_VERSION + 1, "tasks": [] });
+
+        assert!(matches!(
+            migrate(&mut value),
+            Err(LoadError::UnsupportedVersion(version)) if version == CURRENT_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn refuses_a_version_that_is_not_an_integer() {
+        for version in [json!("2"), json!(1.5), json!(-1), json!(null)] {
+            let mut value = json!({ "version": version, "tasks": [] });
+
+            assert!(matches!(migrate(&mut value), Err(LoadError::FormatError)));
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use crate::backup::{self, Backup};
+use crate::migration::{migrate, CURRENT_VERSION};
+use crate::{Filter, Importance, ListId, Sort, TagFilter, Task, TaskList};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Numbers the temporary files, so writes running at the same time in one
+/// process never share one.
+static WRITES: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedState {
+    /// Layout of the file, upgraded on load by the `migration` module.
+    pub version: u32,
+    pub input_value: String,
+    /// The filter of the current list.
+    pub filter: Filter,
+    #[serde(default)]
+    pub sort: Sort,
+    /// Importance levels shown in the list; empty shows all of them.
+    #[serde(default)]
+    pub importances: Vec<Importance>,
+    /// Tags selected in the sidebar.
+    #[serde(default)]
+    pub tags: TagFilter,
+    /// Every list but the inbox may be missing, which is then recreated.
+    #[serde(default)]
+    pub lists: Vec<TaskList>,
+    /// The list shown in the app.
+    #[serde(default)]
+    pub current_list: ListId,
+    pub tasks: Vec<Task>,
+    /// Whether completing the last subtask of a task completes the task.
+    #[serde(default)]
+    pub auto_complete: bool,
+    /// How many rolling backups `save` keeps next to the file.
+    #[serde(default = "default_backup_count")]
+    pub backup_count: usize,
+}
+
+fn default_backup_count() -> usize {
+    3
+}
+
+impl Default for SavedState {
+    fn default() -> Self {
+        SavedState {
+            version: CURRENT_VERSION,
+            input_value: String::new(),
+            filter: Filter::default(),
+            sort: Sort::default(),
+            importances: Vec::new(),
+            tags: TagFilter::default(),
+            lists: Vec::new(),
+            current_list: ListId::INBOX,
+            tasks: Vec::new(),
+            auto_complete: false,
+            backup_count: default_backup_count(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum LoadError {
+    FileError,
+    FormatError,
+    /// The file was written by a newer build that knows a later layout.
+    UnsupportedVersion(u32),
+}
+
+#[derive(Debug, Clone)]
+pub enum SaveError {
+    FileError,
+    WriteError,
+    FormatError,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::FileError => write!(f, "could not read the todo file"),
+            LoadError::FormatError => write!(f, "the todo file is not valid"),
+            LoadError::UnsupportedVersion(version) => write!(
+                f,
+                "the todo file has version {}, newer than the supported {}",
+                version, CURRENT_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::FileError => write!(f, "could not create the todo file"),
+            SaveError::WriteError => write!(f, "could not write the todo file"),
+            SaveError::FormatError => write!(f, "could not serialize the tasks"),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+/// Name of the todo file a project can carry in its own directory.
+pub const PROJECT_FILE: &str = ".ex_todo.json";
+
+impl SavedState {
+    /// The todo file shared by every directory, in the user's data directory.
+    pub fn default_path() -> PathBuf {
+        let mut path = if let Some(project_dirs) = crate::project_dirs() {
+            project_dirs.data_dir().into()
+        } else {
+            std::env::current_dir().unwrap_or_default()
+        };
+
+        path.push("todos.json");
+
+        path
+    }
+
+    /// The [`PROJECT_FILE`] of the current directory if there is one, and
+    /// the [default file](SavedState::default_path) otherwise.
+    pub fn discover() -> PathBuf {
+        match std::env::current_dir() {
+            Ok(dir) if dir.join(PROJECT_FILE).is_file() => dir.join(PROJECT_FILE),
+            _ => Self::default_path(),
+        }
+    }
+
+    /// Reads the todo file at `path`, writing it back when it had to be
+    /// upgraded.
+    pub fn load(path: &Path) -> Result<SavedState, LoadError> {
+        let (state, migrated) = Self::read(path)?;
+
+        if migrated {
+            let _ = state.save(path);
+        }
+
+        Ok(state)
+    }
+
+    /// Reads and upgrades any todo file, e.g. a backup, without writing it back.
+    pub fn load_from(path: &Path) -> Result<SavedState, LoadError> {
+        Self::read(path).map(|(state, _)| state)
+    }
+
+    fn read(path: &Path) -> Result<(SavedState, bool), LoadError> {
+        let mut contents = String::new();
+
+        let mut file = std::fs::File::open(path).map_err(|_| LoadError::FileError)?;
+
+        file.read_to_string(&mut contents)
+            .map_err(|_| LoadError::FileError)?;
+
+        let mut value: serde_json::Value =
+            serde_json::from_str(&contents).map_err(|_| LoadError::FormatError)?;
+
+        let migrated = migrate(&mut value)?;
+
+        let state = serde_json::from_value(value).map_err(|_| LoadError::FormatError)?;
+
+        Ok((state, migrated))
+    }
+
+    /// The backups of the todo file at `path`, with the copies put aside by
+    /// [`save_aside`](SavedState::save_aside) and
+    /// [`backup_unreadable`](SavedState::backup_unreadable) first.
+    pub fn backups(path: &Path) -> Vec<Backup> {
+        backup::list(path)
+    }
+
+    /// Copies the file at `path` next to itself under a timestamped name, so
+    /// an unreadable file survives being replaced.
+    pub fn backup_unreadable(path: &Path) -> std::io::Result<PathBuf> {
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(Local::now().format(".unreadable-%Y%m%d-%H%M%S").to_string());
+
+        std::fs::copy(path, &backup)?;
+
+        Ok(backup.into())
+    }
+
+    /// Writes the tasks next to the todo file at `path` under a timestamped
+    /// name, for changes that could not go into the file itself.
+    pub fn save_aside(&self, path: &Path) -> Result<PathBuf, SaveError> {
+        let mut aside = path.as_os_str().to_owned();
+        aside.push(Local::now().format(".unsaved-%Y%m%d-%H%M%S").to_string());
+        let aside = PathBuf::from(aside);
+
+        self.save(&aside)?;
+
+        Ok(aside)
+    }
+
+    /// Replaces the todo file without ever leaving a partially written one
+    /// behind: the tasks go to a temporary file in the same directory, which
+    /// is flushed to disk and then renamed over the original.
+    pub fn save(&self, path: &Path) -> Result<(), SaveError> {
+        let json = serde_json::to_string_pretty(self).map_err(|_| SaveError::FormatError)?;
+
+        let dir = path.parent().ok_or(SaveError::FileError)?;
+
+        std::fs::create_dir_all(dir).map_err(|_| SaveError::FileError)?;
+
+        let mut temp = path.as_os_str().to_owned();
+        temp.push(format!(
+            ".{}-{}.tmp",
+            std::process::id(),
+            WRITES.fetch_add(1, Ordering::Relaxed)
+        ));
+        let temp = PathBuf::from(temp);
+
+        let written = std::fs::File::create(&temp)
+            .map_err(|_| SaveError::FileError)
+            .and_then(|mut file| {
+                file.write_all(json.as_bytes())
+                    .and_then(|()| file.sync_all())
+                    .map_err(|_| SaveError::WriteError)
+            });
+
+        if let Err(error) = written {
+            let _ = std::fs::remove_file(&temp);
+            return Err(error);
+        }
+
+        // A failed backup must not keep the new tasks from being saved.
+        let _ = backup::rotate(path, self.backup_count);
+
+        if std::fs::rename(&temp, path).is_err() {
+            let _ = std::fs::remove_file(&temp);
+            return Err(SaveError::WriteError);
+        }
+
+        // Make the rename itself durable.
+        #[cfg(unix)]
+        if let Ok(dir) = std::fs::File::open(dir) {
+            let _ = dir.sync_all();
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+use crate::Task;
+use serde::{Deserialize, Serialize};
+use std::cmp::{Ordering, Reverse};
+use std::fmt;
+use std::str::FromStr;
+
+/// Order in which the tasks accepted by a [`crate::Filter`] are shown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Sort {
+    /// The order the tasks are stored in, rearranged by hand.
+    #[default]
+    Manual,
+    /// High before Normal before Low.
+    Importance,
+    /// Newest first.
+    Created,
+    /// Soonest first, tasks without a due date last.
+    Due,
+    /// Most recently completed first, open tasks last.
+    Completed,
+    /// A to Z, ignoring case.
+    Description,
+}
+
+impl Sort {
+    pub const ALL: [Sort; 6] = [
+        Sort::Manual,
+        Sort::Importance,
+        Sort::Created,
+        Sort::Due,
+        Sort::Completed,
+        Sort::Description,
+    ];
+
+    /// Sorts `tasks` in place. Ties keep their stored order.
+    pub fn apply(&self, tasks: &mut [&Task]) {
+        match self {
+            Sort::Manual => {}
+            Sort::Importance => tasks.sort_by_key(|task| Reverse(task.importance)),
+            Sort::Created => tasks.sort_by_key(|task| Reverse(task.created_at)),
+            Sort::Due => tasks.sort_by(|a, b| match (a.due, b.due) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            }),
+            Sort::Completed => tasks.sort_by_key(|task| Reverse(task.completed_at)),
+            Sort::Description => tasks.sort_by_cached_key(|task| task.description.to_lowercase()),
+        }
+    }
+}
+
+impl fmt::Display for Sort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Sort::Manual => "Manual",
+            Sort::Importance => "Importance",
+            Sort::Created => "Created",
+            Sort::Due => "Due date",
+            Sort::Completed => "Completed",
+            Sort::Description => "A-Z",
+        })
+    }
+}
+
+impl FromStr for Sort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "manual" => Ok(Sort::Manual),
+            "importance" => Ok(Sort::Importance),
+            "created" => Ok(Sort::Created),
+            "due" => Ok(Sort::Due),
+            "completed" => Ok(Sort::Completed),
+            "description" | "a-z" => Ok(Sort::Description),
+            _ => Err(format!(
+                "unknown sort `{}` (expected manual, importance, created, due, completed or description)",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Importance;
+    use chrono::{Duration, Local, NaiveDate};
+
+    fn task(description: &str, importance: Importance, due: Option<NaiveDate>) -> Task {
+        Task::new(description.to_string(), Vec::new(), importance, due)
+    }
+
+    fn sorted(sort: Sort, tasks: &[Task]) -> Vec<&str> {
+        let mut tasks = tasks.iter().collect::<Vec<_>>();
+        sort.apply(&mut tasks);
+
+        tasks
+            .into_iter()
+            .map(|task| task.description.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn keeps_the_stored_order_when_manual() {
+        let tasks = [
+            task("b", Importance::Low, None),
+            task("a", Importance::High, None),
+        ];
+
+        assert_eq!(sorted(Sort::Manual, &tasks), ["b", "a"]);
+    }
+
+    #[test]
+    fn puts_the_most_important_first_keeping_ties_in_order() {
+        let tasks = [
+            task("low", Importance::Low, None),
+            task("normal 1", Importance::Normal, None),
+            task("high", Importance::High, None),
+            task("normal 2", Importance::Normal, None),
+        ];
+
+        assert_eq!(
+            sorted(Sort::Importance, &tasks),
+            ["high", "normal 1", "normal 2", "low"]
+        );
+    }
+
+    #[test]
+    fn puts_the_newest_first() {
+        let mut old = task("old", Importance::Normal, None);
+        old.created_at = Local::now() - Duration::days(1);
+        let tasks = [old, task("new", Importance::Normal, None)];
+
+        assert_eq!(sorted(Sort::Created, &tasks), ["new", "old"]);
+    }
+
+    #[test]
+    fn puts_the_soonest_due_first_and_undated_last() {
+        let day = |d| NaiveDate::from_ymd(2021, 12, d);
+        let tasks = [
+            task("none", Importance::Normal, None),
+            task("late", Importance::Normal, Some(day(24))),
+            task("soon", Importance::Normal, Some(day(1))),
+        ];
+
+        assert_eq!(sorted(Sort::Due, &tasks), ["soon", "late", "none"]);
+    }
+
+    #[test]
+    fn puts_the_last_completed_first_and_open_tasks_last() {
+        let mut first = task("first", Importance::Normal, None);
+        first.set_completed(true);
+        first.completed_at = first.completed_at.map(|at| at - Duration::hours(1));
+        let mut last = task("last", Importance::Normal, None);
+        last.set_completed(true);
+        let tasks = [task("open", Importance::Normal, None), first, last];
+
+        assert_eq!(sorted(Sort::Completed, &tasks), ["last", "first", "open"]);
+    }
+
+    #[test]
+    fn sorts_descriptions_ignoring_case() {
+        let tasks = [
+            task("banana", Importance::Normal, None),
+            task("Cherry", Importance::Normal, None),
+            task("apple", Importance::Normal, None),
+        ];
+
+        assert_eq!(
+            sorted(Sort::Description, &tasks),
+            ["apple", "banana", "Cherry"]
+        );
+    }
+}
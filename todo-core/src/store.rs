@@ -0,0 +1,567 @@
+use crate::attachment::display_name;
+use crate::{
+    parse_description, Attachment, Filter, Importance, ListId, Sort, Subtask, TagFilter, Target,
+    Task, TaskId, TaskList,
+};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+/// The list of tasks together with every mutation a front-end may apply.
+///
+/// Tasks keep their insertion order and are addressed by their [`TaskId`],
+/// so a message aimed at one task can never land on another after the list
+/// has been reordered or filtered.
+///
+/// Every task belongs to one of the named [`TaskList`]s, the inbox always
+/// being among them.
+#[derive(Debug, Clone)]
+pub struct TaskStore {
+    lists: Vec<TaskList>,
+    tasks: Vec<Task>,
+}
+
+impl Default for TaskStore {
+    fn default() -> Self {
+        TaskStore::new(Vec::new())
+    }
+}
+
+impl TaskStore {
+    /// Wraps loaded tasks, all in the inbox.
+    pub fn new(tasks: Vec<Task>) -> Self {
+        TaskStore::with_lists(Vec::new(), tasks)
+    }
+
+    /// Wraps loaded lists and tasks, giving a fresh id to any task whose id
+    /// collides with an earlier one (e.g. a task duplicated by hand in the
+    /// file) and moving tasks of unknown lists to the inbox.
+    pub fn with_lists(mut lists: Vec<TaskList>, mut tasks: Vec<Task>) -> Self {
+        if !lists.iter().any(|list| list.id == ListId::INBOX) {
+            lists.insert(0, TaskList::inbox());
+        }
+
+        let mut seen = HashSet::new();
+
+        for task in &mut tasks {
+            while !seen.insert(task.id) {
+                task.id = TaskId::new();
+            }
+
+            if !lists.iter().any(|list| list.id == task.list) {
+                task.list = ListId::INBOX;
+            }
+        }
+
+        TaskStore { lists, tasks }
+    }
+
+    pub fn tasks(&self) -> &[Task] {
+        &self.tasks
+    }
+
+    pub fn lists(&self) -> &[TaskList] {
+        &self.lists
+    }
+
+    pub fn list(&self, id: ListId) -> Option<&TaskList> {
+        self.lists.iter().find(|list| list.id == id)
+    }
+
+    fn list_mut(&mut self, id: ListId) -> Option<&mut TaskList> {
+        self.lists.iter_mut().find(|list| list.id == id)
+    }
+
+    /// Appends a list named `name` and returns its id.
+    pub fn add_list(&mut self, name: String) -> ListId {
+        let list = TaskList::new(name);
+        let id = list.id;
+        self.lists.push(list);
+        id
+    }
+
+    pub fn rename_list(&mut self, id: ListId, name: String) -> bool {
+        match self.list_mut(id) {
+            Some(list) => {
+                list.name = name;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Archives or restores a list; the inbox cannot be archived.
+    pub fn archive_list(&mut self, id: ListId, archived: bool) -> bool {
+        if id == ListId::INBOX {
+            return false;
+        }
+
+        match self.list_mut(id) {
+            Some(list) => {
+                list.archived = archived;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remembers the filter last chosen for a list.
+    pub fn set_list_filter(&mut self, id: ListId, filter: Filter) -> bool {
+        match self.list_mut(id) {
+            Some(list) => {
+                list.filter = filter;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes a list together with its tasks; the inbox cannot be deleted.
+    pub fn delete_list(&mut self, id: ListId) -> Option<(TaskList, Vec<Task>)> {
+        if id == ListId::INBOX {
+            return None;
+        }
+
+        let index = self.lists.iter().position(|list| list.id == id)?;
+        let list = self.lists.remove(index);
+        let (tasks, kept) = std::mem::take(&mut self.tasks)
+            .into_iter()
+            .partition(|task| task.list == id);
+        self.tasks = kept;
+
+        Some((list, tasks))
+    }
+
+    /// Moves a task to another existing list.
+    pub fn move_to_list(&mut self, id: TaskId, list: ListId) -> bool {
+        if self.list(list).is_none() {
+            return false;
+        }
+
+        match self.get_mut(id) {
+            Some(task) if task.list != list => {
+                task.list = list;
+                task.touch();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn get(&self, id: TaskId) -> Option<&Task> {
+        self.tasks.iter().find(|task| task.id == id)
+    }
+
+    fn get_mut(&mut self, id: TaskId) -> Option<&mut Task> {
+        self.tasks.iter_mut().find(|task| task.id == id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Appends `task` and returns its id.
+    pub fn add(&mut self, task: Task) -> TaskId {
+        let id = task.id;
+        self.tasks.push(task);
+        id
+    }
+
+    /// Replaces the description of a task with `typed`: the `#tags` in it
+    /// join the task's tags, and its links the attachments.
+    pub fn edit(&mut self, id: TaskId, typed: &str) -> bool {
+        let typed = parse_description(typed);
+
+        match self.get_mut(id) {
+            Some(task) => {
+                task.description = typed.text;
+                task.tags.extend(typed.tags);
+
+                for link in typed.links {
+                    if !task.attachments.contains(&link) {
+                        task.attachments.push(link);
+                    }
+                }
+
+                task.touch();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn complete(&mut self, id: TaskId, completed: bool) -> bool {
+        match self.get_mut(id) {
+            Some(task) => {
+                task.set_completed(completed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn add_subtask(&mut self, id: TaskId, description: String) -> bool {
+        match self.get_mut(id) {
+            Some(task) => {
+                task.subtasks.push(Subtask {
+                    description,
+                    completed: false,
+                });
+                task.touch();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Checks or unchecks a subtask. With `auto_complete` the task itself is
+    /// then completed exactly when all of its subtasks are.
+    pub fn complete_subtask(
+        &mut self,
+        id: TaskId,
+        index: usize,
+        completed: bool,
+        auto_complete: bool,
+    ) -> bool {
+        let Some(task) = self.get_mut(id) else {
+            return false;
+        };
+        let Some(subtask) = task.subtasks.get_mut(index) else {
+            return false;
+        };
+
+        subtask.completed = completed;
+        task.touch();
+
+        if auto_complete {
+            let all_done = task.subtasks.iter().all(|subtask| subtask.completed);
+            task.set_completed(all_done);
+        }
+
+        true
+    }
+
+    pub fn delete_subtask(&mut self, id: TaskId, index: usize) -> Option<Subtask> {
+        let task = self.get_mut(id)?;
+
+        if index >= task.subtasks.len() {
+            return None;
+        }
+
+        task.touch();
+
+        Some(task.subtasks.remove(index))
+    }
+
+    pub fn attach(&mut self, id: TaskId, attachment: Attachment) -> bool {
+        match self.get_mut(id) {
+            Some(task) => {
+                task.attachments.push(attachment);
+                task.touch();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Re-points attachments at the missing file `old` to `new`, found
+    /// elsewhere by the user.
+    ///
+    /// Files are usually moved together with their directory, so any other
+    /// missing file under `old`'s directory that exists at the same place
+    /// under `new`'s directory is re-pointed as well. Returns how many
+    /// attachments changed.
+    pub fn relocate(&mut self, old: &Path, new: &Path) -> usize {
+        let (old_dir, new_dir) = match (old.parent(), new.parent()) {
+            (Some(old_dir), Some(new_dir)) => (old_dir, new_dir),
+            _ => return 0,
+        };
+        let mut relocated = 0;
+
+        for task in &mut self.tasks {
+            let mut changed = false;
+
+            for attachment in &mut task.attachments {
+                let Target::LocalFile { path } = &mut attachment.target else {
+                    continue;
+                };
+
+                let moved_to = if path == old {
+                    new.to_path_buf()
+                } else {
+                    match path.strip_prefix(old_dir) {
+                        Ok(relative) if !path.exists() && new_dir.join(relative).exists() => {
+                            new_dir.join(relative)
+                        }
+                        _ => continue,
+                    }
+                };
+
+                if attachment.name == display_name(path) {
+                    attachment.name = display_name(&moved_to);
+                }
+
+                *path = moved_to;
+                changed = true;
+                relocated += 1;
+            }
+
+            if changed {
+                task.touch();
+            }
+        }
+
+        relocated
+    }
+
+    /// Overwrites the task with the same id, e.g. with the result of an
+    /// editor working on a copy.
+    pub fn replace(&mut self, task: Task) -> bool {
+        match self.get_mut(task.id) {
+            Some(current) => {
+                *current = task;
+                current.touch();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Exchanges the positions of two tasks, for manual ordering.
+    pub fn swap(&mut self, a: TaskId, b: TaskId) -> bool {
+        let position = |id| self.tasks.iter().position(|task: &Task| task.id == id);
+
+        match (position(a), position(b)) {
+            (Some(a), Some(b)) => {
+                self.tasks.swap(a, b);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn delete(&mut self, id: TaskId) -> Option<Task> {
+        let index = self.tasks.iter().position(|task| task.id == id)?;
+
+        Some(self.tasks.remove(index))
+    }
+
+    /// Tasks of `list` (of every list if `None`) accepted by `filter`, the
+    /// free-text `word` filter and the selected `importances` and `tags`, in
+    /// the order given by `sort`.
+    pub fn sorted<'a>(
+        &'a self,
+        list: Option<ListId>,
+        filter: Filter,
+        word: &'a str,
+        importances: &'a [Importance],
+        tags: &'a TagFilter,
+        sort: Sort,
+    ) -> Vec<&'a Task> {
+        let mut tasks = self
+            .query(list, filter, word, importances, tags)
+            .collect::<Vec<_>>();
+
+        sort.apply(&mut tasks);
+
+        tasks
+    }
+
+    /// Tasks of `list` (of every list if `None`) accepted by `filter`, the
+    /// free-text `word` filter and the selected `importances` and `tags`.
+    pub fn query<'a>(
+        &'a self,
+        list: Option<ListId>,
+        filter: Filter,
+        word: &'a str,
+        importances: &'a [Importance],
+        tags: &'a TagFilter,
+    ) -> impl Iterator<Item = &'a Task> + 'a {
+        self.tasks.iter().filter(move |task| {
+            list.is_none_or(|list| task.list == list)
+                && filter.matches(task)
+                && filter.word_matches(task, word)
+                && filter.importance_matches(task, importances)
+                && tags.matches(task)
+        })
+    }
+
+    /// Uncompleted tasks of `list` (of every list if `None`) among those the
+    /// `word`, `importances` and `tags` filters let through.
+    pub fn tasks_left(
+        &self,
+        list: Option<ListId>,
+        word: &str,
+        importances: &[Importance],
+        tags: &TagFilter,
+    ) -> usize {
+        self.query(list, Filter::Active, word, importances, tags)
+            .count()
+    }
+
+    /// Overdue tasks of `list` (of every list if `None`) among those the
+    /// `word`, `importances` and `tags` filters let through.
+    pub fn overdue(
+        &self,
+        list: Option<ListId>,
+        word: &str,
+        importances: &[Importance],
+        tags: &TagFilter,
+    ) -> usize {
+        self.query(list, Filter::Overdue, word, importances, tags)
+            .count()
+    }
+
+    /// Every tag in use in `list` (in every list if `None`), with the number
+    /// of its tasks carrying it.
+    pub fn tag_counts(&self, list: Option<ListId>) -> BTreeMap<&str, usize> {
+        let mut counts = BTreeMap::new();
+
+        for tag in self
+            .tasks
+            .iter()
+            .filter(|task| list.is_none_or(|list| task.list == list))
+            .flat_map(|task| &task.tags)
+        {
+            *counts.entry(tag.as_str()).or_insert(0) += 1;
+        }
+
+        counts
+    }
+}
+
+impl From<TaskStore> for Vec<Task> {
+    fn from(store: TaskStore) -> Vec<Task> {
+        store.tasks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Importance;
+    use std::path::PathBuf;
+
+    /// A scratch directory, removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let dir =
+                std::env::temp_dir().join(format!("ex_todo-store-{}-{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+
+            TempDir(dir)
+        }
+
+        /// Creates the file at `relative`, with its directories.
+        fn file(&self, relative: &str) -> PathBuf {
+            let path = self.0.join(relative);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, "").unwrap();
+
+            path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn task_with(paths: &[&Path]) -> Task {
+        Task::new(
+            String::from("task"),
+            paths
+                .iter()
+                .map(|path| Attachment::new(path.to_path_buf()))
+                .collect(),
+            Importance::Normal,
+            None,
+        )
+    }
+
+    fn paths(store: &TaskStore) -> Vec<PathBuf> {
+        store
+            .tasks()
+            .iter()
+            .flat_map(|task| &task.attachments)
+            .filter_map(|attachment| attachment.path())
+            .map(Path::to_path_buf)
+            .collect()
+    }
+
+    #[test]
+    fn relocates_the_missing_file_to_where_it_was_found() {
+        let dir = TempDir::new("found");
+        let old = dir.0.join("old").join("report.pdf");
+        let new = dir.file("new/report-final.pdf");
+        let mut store = TaskStore::new(vec![task_with(&[&old]), task_with(&[&old])]);
+
+        assert_eq!(store.relocate(&old, &new), 2);
+        assert_eq!(paths(&store), [new.clone(), new]);
+        assert_eq!(store.tasks()[0].attachments[0].name, "report-final.pdf");
+    }
+
+    #[test]
+    fn relocates_the_missing_files_moved_along_with_it() {
+        let dir = TempDir::new("siblings");
+        let old = dir.0.join("old").join("report.pdf");
+        let moved = dir.0.join("old").join("data").join("sheet.csv");
+        let gone = dir.0.join("old").join("gone.txt");
+        let new = dir.file("new/report.pdf");
+        let moved_to = dir.file("new/data/sheet.csv");
+        let mut store = TaskStore::new(vec![task_with(&[&old, &moved, &gone])]);
+
+        assert_eq!(store.relocate(&old, &new), 2);
+        assert_eq!(paths(&store), [new, moved_to, gone]);
+    }
+
+    #[test]
+    fn leaves_files_that_still_exist_alone() {
+        let dir = TempDir::new("exists");
+        let old = dir.0.join("old").join("report.pdf");
+        let present = dir.file("old/notes.txt");
+        dir.file("new/notes.txt");
+        let new = dir.file("new/report.pdf");
+        let mut store = TaskStore::new(vec![task_with(&[&old, &present])]);
+
+        assert_eq!(store.relocate(&old, &new), 1);
+        assert_eq!(paths(&store), [new, present]);
+    }
+
+    #[test]
+    fn keeps_the_name_given_to_an_attachment() {
+        let dir = TempDir::new("renamed");
+        let old = dir.0.join("old").join("report.pdf");
+        let new = dir.file("new/report-final.pdf");
+        let mut task = task_with(&[&old]);
+        task.attachments[0].name = String::from("Q3 report");
+        let mut store = TaskStore::new(vec![task]);
+
+        store.relocate(&old, &new);
+
+        assert_eq!(store.tasks()[0].attachments[0].name, "Q3 report");
+    }
+
+    #[test]
+    fn relocates_nothing_when_no_task_has_the_file() {
+        let dir = TempDir::new("none");
+        let other = dir.0.join("elsewhere").join("x.txt");
+        let new = dir.file("new/report.pdf");
+        let mut store = TaskStore::new(vec![task_with(&[&other])]);
+        let before = store.tasks()[0].updated_at;
+
+        assert_eq!(
+            store.relocate(&dir.0.join("old").join("report.pdf"), &new),
+            0
+        );
+        assert_eq!(paths(&store), [other]);
+        assert_eq!(store.tasks()[0].updated_at, before);
+    }
+}
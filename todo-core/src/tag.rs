@@ -0,0 +1,162 @@
+use crate::Task;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+/// The tags selected to filter tasks by, and how they combine.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagFilter {
+    pub tags: BTreeSet<String>,
+    pub mode: TagMode,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TagMode {
+    /// Tasks with at least one of the selected tags.
+    #[default]
+    Any,
+    /// Tasks with every selected tag.
+    All,
+}
+
+impl FromStr for TagMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "any" | "or" => Ok(TagMode::Any),
+            "all" | "and" => Ok(TagMode::All),
+            _ => Err(format!("unknown tag mode `{}` (expected any or all)", s)),
+        }
+    }
+}
+
+impl TagFilter {
+    /// Whether `task` carries the selected tags; selecting none shows every
+    /// task.
+    pub fn matches(&self, task: &Task) -> bool {
+        if self.tags.is_empty() {
+            return true;
+        }
+
+        match self.mode {
+            TagMode::Any => self.tags.iter().any(|tag| task.tags.contains(tag)),
+            TagMode::All => self.tags.iter().all(|tag| task.tags.contains(tag)),
+        }
+    }
+
+    /// Selects `tag`, or unselects it when it already is.
+    pub fn toggle(&mut self, tag: &str) {
+        if !self.tags.remove(tag) {
+            self.tags.insert(tag.to_string());
+        }
+    }
+}
+
+/// Splits the `#tags` out of a typed description, returning the remaining
+/// text and the tags, lowercased and without the `#`.
+pub fn extract_tags(text: &str) -> (String, BTreeSet<String>) {
+    let mut words = Vec::new();
+    let mut tags = BTreeSet::new();
+
+    for word in text.split_whitespace() {
+        match parse_tag(word) {
+            Some(tag) => {
+                tags.insert(tag);
+            }
+            None => words.push(word),
+        }
+    }
+
+    if tags.is_empty() {
+        (text.to_string(), tags)
+    } else {
+        (words.join(" "), tags)
+    }
+}
+
+/// Reads `#client-a` as `client-a`. Issue numbers such as `#123` are not
+/// tags.
+pub fn parse_tag(word: &str) -> Option<String> {
+    let tag = word.strip_prefix('#')?;
+
+    let valid = tag
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+        && !tag.chars().all(|c| c.is_ascii_digit());
+
+    valid.then(|| tag.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Importance;
+
+    fn tagged(tags: &[&str]) -> Task {
+        let mut task = Task::new(String::from("task"), Vec::new(), Importance::Normal, None);
+        task.tags = tags.iter().map(|tag| tag.to_string()).collect();
+
+        task
+    }
+
+    fn filter(tags: &[&str], mode: TagMode) -> TagFilter {
+        TagFilter {
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            mode,
+        }
+    }
+
+    #[test]
+    fn reads_tags_lowercased() {
+        assert_eq!(parse_tag("#Client-A"), Some(String::from("client-a")));
+        assert_eq!(parse_tag("#to_do"), Some(String::from("to_do")));
+        assert_eq!(parse_tag("#v2"), Some(String::from("v2")));
+        assert_eq!(parse_tag("#日本語"), Some(String::from("日本語")));
+    }
+
+    #[test]
+    fn leaves_out_issue_numbers_and_other_words() {
+        for word in ["#123", "#", "tag", "a#b", "#tag!", "#a/b", "##tag"] {
+            assert_eq!(parse_tag(word), None, "{}", word);
+        }
+    }
+
+    #[test]
+    fn extracts_tags_from_the_text() {
+        let (text, tags) = extract_tags("Review #Client-A report #review for #123");
+
+        assert_eq!(text, "Review report for #123");
+        assert_eq!(tags.into_iter().collect::<Vec<_>>(), ["client-a", "review"]);
+    }
+
+    #[test]
+    fn leaves_text_without_tags_as_typed() {
+        let (text, tags) = extract_tags("  fix  #42 ");
+
+        assert_eq!(text, "  fix  #42 ");
+        assert!(tags.is_empty());
+    }
+
+    #[test]
+    fn matches_any_or_all_of_the_selected_tags() {
+        let task = tagged(&["client-a", "review"]);
+
+        assert!(filter(&[], TagMode::All).matches(&task));
+        assert!(filter(&["review", "urgent"], TagMode::Any).matches(&task));
+        assert!(!filter(&["review", "urgent"], TagMode::All).matches(&task));
+        assert!(filter(&["review", "client-a"], TagMode::All).matches(&task));
+        assert!(!filter(&["urgent"], TagMode::Any).matches(&tagged(&[])));
+    }
+
+    #[test]
+    fn toggles_a_tag() {
+        let mut filter = TagFilter::default();
+
+        filter.toggle("review");
+        assert!(filter.tags.contains("review"));
+
+        filter.toggle("review");
+        assert!(filter.tags.is_empty());
+    }
+}
@@ -0,0 +1,200 @@
+use crate::{extract_links, extract_tags, Attachment, ListId};
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Importance {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl Importance {
+    pub fn all() -> [Importance; 3] {
+        [Importance::Low, Importance::Normal, Importance::High]
+    }
+}
+
+impl From<Importance> for String {
+    fn from(importance: Importance) -> String {
+        String::from(match importance {
+            Importance::Low => "Low",
+            Importance::Normal => "Normal",
+            Importance::High => "High",
+        })
+    }
+}
+
+impl FromStr for Importance {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "low" => Ok(Importance::Low),
+            "normal" => Ok(Importance::Normal),
+            "high" => Ok(Importance::High),
+            _ => Err(format!(
+                "unknown importance `{}` (expected low, normal or high)",
+                s
+            )),
+        }
+    }
+}
+
+/// Persisted identifier of a task, stable across reordering and filtering.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TaskId(Uuid);
+
+impl TaskId {
+    pub fn new() -> Self {
+        TaskId(Uuid::new_v4())
+    }
+}
+
+impl Default for TaskId {
+    fn default() -> Self {
+        TaskId::new()
+    }
+}
+
+impl fmt::Display for TaskId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for TaskId {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Uuid::parse_str(s).map(TaskId)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Task {
+    pub id: TaskId,
+    pub description: String,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    pub completed: bool,
+    pub importance: Importance,
+    pub created_at: DateTime<Local>,
+    pub updated_at: DateTime<Local>,
+    pub completed_at: Option<DateTime<Local>>,
+    #[serde(default)]
+    pub due: Option<NaiveDate>,
+    #[serde(default)]
+    pub subtasks: Vec<Subtask>,
+    /// Lowercase, without the leading `#`.
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
+    #[serde(default)]
+    pub list: ListId,
+}
+
+/// A checklist item of a task.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Subtask {
+    pub description: String,
+    pub completed: bool,
+}
+
+impl Task {
+    pub fn new(
+        description: String,
+        attachments: Vec<Attachment>,
+        importance: Importance,
+        due: Option<NaiveDate>,
+    ) -> Self {
+        let now = Local::now();
+
+        Task {
+            id: TaskId::new(),
+            description,
+            completed: false,
+            attachments,
+            importance,
+            created_at: now,
+            updated_at: now,
+            completed_at: None,
+            due,
+            subtasks: Vec::new(),
+            tags: BTreeSet::new(),
+            list: ListId::INBOX,
+        }
+    }
+
+    /// Marks the task as modified right now.
+    pub fn touch(&mut self) {
+        self.updated_at = Local::now();
+    }
+
+    /// Whether the task is still open past its due date.
+    pub fn is_overdue(&self, today: NaiveDate) -> bool {
+        !self.completed && self.due.is_some_and(|due| due < today)
+    }
+
+    /// Completed and total subtasks, or `None` for a task without any.
+    pub fn progress(&self) -> Option<(usize, usize)> {
+        if self.subtasks.is_empty() {
+            return None;
+        }
+
+        let done = self
+            .subtasks
+            .iter()
+            .filter(|subtask| subtask.completed)
+            .count();
+
+        Some((done, self.subtasks.len()))
+    }
+
+    pub fn set_completed(&mut self, completed: bool) {
+        if self.completed != completed {
+            self.completed = completed;
+            self.completed_at = completed.then(Local::now);
+            self.touch();
+        }
+    }
+}
+
+/// A description as typed, with its `#tags` and links taken out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedDescription {
+    pub text: String,
+    pub tags: BTreeSet<String>,
+    pub links: Vec<Attachment>,
+}
+
+/// Splits the `#tags` and links out of a typed description. When nothing
+/// else was typed, the first link's name, or else the text as typed, stays
+/// the description.
+pub fn parse_description(typed: &str) -> TypedDescription {
+    let (text, tags) = extract_tags(typed);
+    let (mut text, links) = extract_links(&text);
+
+    if text.is_empty() {
+        text = match links.first() {
+            Some(link) => link.name.clone(),
+            None => typed.trim().to_string(),
+        };
+    }
+
+    TypedDescription { text, tags, links }
+}
+
+/// Parses a due date typed as `2021/12/24` or `2021-12-24`.
+pub fn parse_due(input: &str) -> Option<NaiveDate> {
+    let input = input.trim();
+
+    NaiveDate::parse_from_str(input, "%Y/%m/%d")
+        .or_else(|_| NaiveDate::parse_from_str(input, "%Y-%m-%d"))
+        .ok()
+}